@@ -0,0 +1,286 @@
+use std::fmt;
+use std::str::FromStr;
+
+// a stage applied to the readings before counting increases
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    // replace readings that deviate from the median of the `window` readings
+    // around them by more than `tolerance` with that median
+    Median { window: usize, tolerance: u64 },
+    // drop readings more than `threshold` standard deviations from the mean
+    ZScore { threshold: f64 },
+    // clamp readings to within `limit` of the reading before them
+    RateOfChange { limit: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    Median { median: u64 },
+    ZScore { score: f64 },
+    RateOfChange { clamped: u64 },
+}
+
+// a reading a filter dropped or replaced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rejection {
+    // index in the unfiltered readings
+    pub index: usize,
+    // the reading as the filter saw it
+    pub value: u64,
+    pub reason: Reason,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{} {}: ", self.index, self.value)?;
+        match self.reason {
+            Reason::Median { median } => write!(f, "replaced by local median {}", median),
+            Reason::ZScore { score } => write!(f, "dropped, z-score {:.2}", score),
+            Reason::RateOfChange { clamped } => write!(f, "clamped to {}", clamped),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filtered {
+    pub readings: Vec<u64>,
+    pub rejections: Vec<Rejection>,
+}
+
+// run the filters in order, each on the output of the one before
+pub fn apply(readings: &[u64], filters: &[Filter]) -> Filtered {
+    let mut indexed: Vec<(usize, u64)> = readings.iter().copied().enumerate().collect();
+    let mut rejections = Vec::new();
+    for filter in filters {
+        indexed = match *filter {
+            Filter::Median { window, tolerance } => {
+                median(&indexed, window, tolerance, &mut rejections)
+            }
+            Filter::ZScore { threshold } => z_score(&indexed, threshold, &mut rejections),
+            Filter::RateOfChange { limit } => rate_of_change(&indexed, limit, &mut rejections),
+        };
+    }
+    Filtered {
+        readings: indexed.into_iter().map(|(_, value)| value).collect(),
+        rejections,
+    }
+}
+
+fn median(
+    readings: &[(usize, u64)],
+    window: usize,
+    tolerance: u64,
+    rejections: &mut Vec<Rejection>,
+) -> Vec<(usize, u64)> {
+    let half = window / 2;
+    (0..readings.len())
+        .map(|i| {
+            // windows are cut short at both ends of the readings
+            let range = i.saturating_sub(half)..(i + half + 1).min(readings.len());
+            let mut values: Vec<u64> = readings[range].iter().map(|&(_, v)| v).collect();
+            let median = stats::median(&mut values).unwrap().lower();
+            let (index, value) = readings[i];
+            if value.abs_diff(median) > tolerance {
+                rejections.push(Rejection {
+                    index,
+                    value,
+                    reason: Reason::Median { median },
+                });
+                return (index, median);
+            }
+            (index, value)
+        })
+        .collect()
+}
+
+fn z_score(
+    readings: &[(usize, u64)],
+    threshold: f64,
+    rejections: &mut Vec<Rejection>,
+) -> Vec<(usize, u64)> {
+    let values: Vec<u64> = readings.iter().map(|&(_, v)| v).collect();
+    let mean = match stats::mean(&values) {
+        Some(mean) => mean,
+        None => return Vec::new(),
+    };
+    let variance = values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64;
+    let deviation = variance.sqrt();
+    if deviation == 0.0 {
+        return readings.to_vec();
+    }
+    readings
+        .iter()
+        .copied()
+        .filter(|&(index, value)| {
+            let score = (value as f64 - mean) / deviation;
+            if score.abs() > threshold {
+                rejections.push(Rejection {
+                    index,
+                    value,
+                    reason: Reason::ZScore { score },
+                });
+                return false;
+            }
+            true
+        })
+        .collect()
+}
+
+fn rate_of_change(
+    readings: &[(usize, u64)],
+    limit: u64,
+    rejections: &mut Vec<Rejection>,
+) -> Vec<(usize, u64)> {
+    let mut previous: Option<u64> = None;
+    readings
+        .iter()
+        .map(|&(index, value)| {
+            let clamped = match previous {
+                Some(p) => value.clamp(p.saturating_sub(limit), p.saturating_add(limit)),
+                None => value,
+            };
+            if clamped != value {
+                rejections.push(Rejection {
+                    index,
+                    value,
+                    reason: Reason::RateOfChange { clamped },
+                });
+            }
+            previous = Some(clamped);
+            (index, clamped)
+        })
+        .collect()
+}
+
+// `median:<window>:<tolerance>`, `zscore:<threshold>` or `rate:<limit>`
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |part: &str| {
+            part.parse::<u64>()
+                .map_err(|_| format!("Could not parse value: {:?}", part))
+        };
+        match parts[..] {
+            ["median", window, tolerance] => Ok(Filter::Median {
+                window: number(window)? as usize,
+                tolerance: number(tolerance)?,
+            }),
+            ["zscore", threshold] => Ok(Filter::ZScore {
+                threshold: threshold
+                    .parse()
+                    .map_err(|_| format!("Could not parse value: {:?}", threshold))?,
+            }),
+            ["rate", limit] => Ok(Filter::RateOfChange {
+                limit: number(limit)?,
+            }),
+            _ => Err(format!("Unknown filter: {:?}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_depth_increases;
+
+    // the example with two spikes
+    fn noisy() -> Vec<u64> {
+        vec![199, 200, 208, 9999, 210, 200, 207, 240, 0, 269, 260, 263]
+    }
+
+    #[test]
+    fn median_filter() {
+        let filtered = apply(
+            &noisy(),
+            &[Filter::Median {
+                window: 3,
+                tolerance: 50,
+            }],
+        );
+        assert_eq!(
+            filtered.readings,
+            vec![199, 200, 208, 210, 210, 200, 207, 240, 240, 269, 260, 263]
+        );
+        let indices: Vec<usize> = filtered.rejections.iter().map(|r| r.index).collect();
+        assert_eq!(indices, vec![3, 8]);
+        assert_eq!(
+            filtered.rejections[0].reason,
+            Reason::Median { median: 210 }
+        );
+        assert_eq!(
+            filtered.rejections[0].to_string(),
+            "#3 9999: replaced by local median 210"
+        );
+    }
+
+    #[test]
+    fn z_score_filter() {
+        let filtered = apply(&noisy(), &[Filter::ZScore { threshold: 2.0 }]);
+        assert_eq!(filtered.rejections.len(), 1);
+        assert_eq!(filtered.rejections[0].index, 3);
+        // the remaining spike is dropped once the first no longer skews the
+        // deviation
+        let filtered = apply(
+            &noisy(),
+            &[
+                Filter::ZScore { threshold: 2.0 },
+                Filter::ZScore { threshold: 2.0 },
+            ],
+        );
+        assert_eq!(
+            filtered.readings,
+            vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263]
+        );
+        assert_eq!(count_depth_increases(&filtered.readings, 1), 7);
+        assert_eq!(count_depth_increases(&filtered.readings, 3), 5);
+    }
+
+    #[test]
+    fn rate_of_change_filter() {
+        let filtered = apply(&noisy(), &[Filter::RateOfChange { limit: 40 }]);
+        assert_eq!(
+            filtered.readings,
+            vec![199, 200, 208, 248, 210, 200, 207, 240, 200, 240, 260, 263]
+        );
+        let reasons: Vec<Reason> = filtered.rejections.iter().map(|r| r.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                Reason::RateOfChange { clamped: 248 },
+                Reason::RateOfChange { clamped: 200 },
+                Reason::RateOfChange { clamped: 240 },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_filters() {
+        let filtered = apply(&noisy(), &[]);
+        assert_eq!(filtered.readings, noisy());
+        assert!(filtered.rejections.is_empty());
+        assert!(apply(&[], &[Filter::ZScore { threshold: 1.0 }])
+            .readings
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_filters() {
+        assert_eq!(
+            "median:5:20".parse(),
+            Ok(Filter::Median {
+                window: 5,
+                tolerance: 20
+            })
+        );
+        assert_eq!("zscore:2.5".parse(), Ok(Filter::ZScore { threshold: 2.5 }));
+        assert_eq!("rate:100".parse(), Ok(Filter::RateOfChange { limit: 100 }));
+        assert!("rate".parse::<Filter>().is_err());
+        assert!("mode:3".parse::<Filter>().is_err());
+    }
+}
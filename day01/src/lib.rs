@@ -0,0 +1,216 @@
+use std::collections::VecDeque;
+
+use stats::Sample;
+
+pub mod filter;
+pub mod plot;
+pub mod report;
+pub mod stream;
+
+// two neighbouring windows share all but their first and last measurement,
+// so comparing their sums is the same as comparing those two.
+pub fn count_depth_increases<T: PartialOrd>(measurements: &[T], window_size: usize) -> usize {
+    measurements
+        .iter()
+        .zip(measurements.iter().skip(window_size))
+        .filter(|(first, last)| last > first)
+        .count()
+}
+
+// change of the window sum from each window to the next
+pub fn window_deltas(measurements: &[u64], window_size: usize) -> impl Iterator<Item = i64> + '_ {
+    measurements
+        .iter()
+        .zip(measurements.iter().skip(window_size))
+        .map(|(&first, &last)| last as i64 - first as i64)
+}
+
+// how a window of measurements is reduced to a single value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregator {
+    Sum,
+    Mean,
+    Max,
+    Min,
+}
+
+// which change from one window's value to the next is counted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Predicate {
+    Increase,
+    Decrease,
+    // increase by more than the threshold
+    IncreaseBy(f64),
+    // decrease by more than the threshold
+    DecreaseBy(f64),
+}
+
+impl Predicate {
+    fn matches(self, a: f64, b: f64) -> bool {
+        match self {
+            Predicate::Increase => b > a,
+            Predicate::Decrease => b < a,
+            Predicate::IncreaseBy(threshold) => b - a > threshold,
+            Predicate::DecreaseBy(threshold) => a - b > threshold,
+        }
+    }
+}
+
+// value of every full window, in O(n) for all aggregators
+pub fn window_aggregates<T: Sample>(
+    data: &[T],
+    window_size: usize,
+    aggregator: Aggregator,
+) -> Vec<f64> {
+    if window_size == 0 || data.len() < window_size {
+        return Vec::new();
+    }
+    let values: Vec<f64> = data.iter().map(|v| v.to_f64()).collect();
+    match aggregator {
+        Aggregator::Sum | Aggregator::Mean => {
+            let divisor = match aggregator {
+                Aggregator::Mean => window_size as f64,
+                _ => 1.0,
+            };
+            let mut sum: f64 = values[..window_size].iter().sum();
+            let mut aggregates = vec![sum / divisor];
+            for i in window_size..values.len() {
+                sum += values[i] - values[i - window_size];
+                aggregates.push(sum / divisor);
+            }
+            aggregates
+        }
+        Aggregator::Max | Aggregator::Min => {
+            // indices of the candidates for the window's extreme, their
+            // values ordered from best to worst
+            let better = |a: f64, b: f64| match aggregator {
+                Aggregator::Max => a >= b,
+                _ => a <= b,
+            };
+            let mut candidates: VecDeque<usize> = VecDeque::new();
+            let mut aggregates = Vec::with_capacity(values.len() - window_size + 1);
+            for (i, &value) in values.iter().enumerate() {
+                while candidates
+                    .back()
+                    .is_some_and(|&last| better(value, values[last]))
+                {
+                    candidates.pop_back();
+                }
+                candidates.push_back(i);
+                if candidates[0] + window_size <= i {
+                    candidates.pop_front();
+                }
+                if i + 1 >= window_size {
+                    aggregates.push(values[candidates[0]]);
+                }
+            }
+            aggregates
+        }
+    }
+}
+
+// generalisation of `count_depth_increases`: number of neighbouring windows
+// whose aggregated values satisfy the predicate
+pub fn count_window_transitions<T: Sample>(
+    data: &[T],
+    window_size: usize,
+    aggregator: Aggregator,
+    predicate: Predicate,
+) -> usize {
+    window_aggregates(data, window_size, aggregator)
+        .windows(2)
+        .filter(|pair| predicate.matches(pair[0], pair[1]))
+        .count()
+}
+
+// the depths of the puzzle's example
+#[cfg(test)]
+pub(crate) const EXAMPLE: [u64; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_pt1() {
+        assert_eq!(count_depth_increases(&EXAMPLE, 1), 7);
+    }
+
+    #[test]
+    fn example_pt2() {
+        assert_eq!(count_depth_increases(&EXAMPLE, 3), 5);
+    }
+
+    #[test]
+    fn short_input() {
+        let input = vec![199, 200, 208];
+        assert_eq!(count_depth_increases(&input, 3), 0);
+        assert_eq!(count_depth_increases(&input, 4), 0);
+        assert_eq!(count_depth_increases::<u32>(&[], 1), 0);
+    }
+
+    #[test]
+    fn other_numeric_types() {
+        let input: [i64; 5] = [-3, -5, -1, 0, -10];
+        assert_eq!(count_depth_increases(&input, 1), 2);
+        assert_eq!(count_depth_increases(&input, 2), 2);
+        let input = [0.5, 0.25, 1.5, 1.0];
+        assert_eq!(count_depth_increases(&input, 1), 1);
+        assert_eq!(count_depth_increases(&input, 2), 2);
+    }
+
+    #[test]
+    fn transitions_match_depth_increases() {
+        let input = EXAMPLE;
+        for window_size in 1..=4 {
+            for aggregator in [Aggregator::Sum, Aggregator::Mean] {
+                assert_eq!(
+                    count_window_transitions(&input, window_size, aggregator, Predicate::Increase),
+                    count_depth_increases(&input, window_size)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn aggregators() {
+        let input: Vec<u32> = vec![3, 1, 4, 1, 5, 9, 2, 6];
+        assert_eq!(
+            window_aggregates(&input, 3, Aggregator::Sum),
+            vec![8.0, 6.0, 10.0, 15.0, 16.0, 17.0]
+        );
+        assert_eq!(
+            window_aggregates(&input, 2, Aggregator::Mean),
+            vec![2.0, 2.5, 2.5, 3.0, 7.0, 5.5, 4.0]
+        );
+        assert_eq!(
+            window_aggregates(&input, 3, Aggregator::Max),
+            vec![4.0, 4.0, 5.0, 9.0, 9.0, 9.0]
+        );
+        assert_eq!(
+            window_aggregates(&input, 3, Aggregator::Min),
+            vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0]
+        );
+        assert!(window_aggregates(&input, 9, Aggregator::Max).is_empty());
+        assert!(window_aggregates(&input, 0, Aggregator::Sum).is_empty());
+    }
+
+    #[test]
+    fn predicates() {
+        let input: Vec<i32> = vec![3, 1, 4, 1, 5, 9, 2, 6];
+        let count =
+            |aggregator, predicate| count_window_transitions(&input, 1, aggregator, predicate);
+        assert_eq!(count(Aggregator::Sum, Predicate::Increase), 4);
+        assert_eq!(count(Aggregator::Sum, Predicate::Decrease), 3);
+        assert_eq!(count(Aggregator::Sum, Predicate::IncreaseBy(3.0)), 3);
+        assert_eq!(count(Aggregator::Sum, Predicate::DecreaseBy(2.0)), 2);
+        assert_eq!(
+            count_window_transitions(&input, 3, Aggregator::Max, Predicate::Increase),
+            2
+        );
+        assert_eq!(
+            count_window_transitions(&input, 3, Aggregator::Min, Predicate::Decrease),
+            0
+        );
+    }
+}
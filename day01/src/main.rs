@@ -1,61 +1,59 @@
+use day01::count_depth_increases;
+use day01::filter::{self, Filter};
+use day01::plot;
+use day01::report::SonarReport;
+use day01::stream::Sweep;
+use std::env;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::Path;
-
-fn read<P>(path: P) -> std::io::Result<String>
-where
-    P: AsRef<Path>,
-{
-    let mut file = File::open(path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    Ok(contents)
-}
+use std::io::BufReader;
 
 fn main() -> std::io::Result<()> {
-    let contents = read("1.in").expect("Could not read input file.");
-    let input: Vec<usize> = contents
-        .lines()
-        .map(|x| x.parse::<usize>().expect("Could not parse value."))
-        .collect();
-    println!("Part1: {:?}", count_depth_increases(&input, 1));
-    println!("Part2: {:?}", count_depth_increases(&input, 3));
-
-    Ok(())
-}
-
-fn count_depth_increases(measurements: &Vec<usize>, window_size: usize) -> usize {
-    let mut count = 0;
-
-    let mut window_a: &[usize];
-    let mut window_b: &[usize];
-
-    for i in 0..measurements.len() - window_size {
-        window_a = &measurements[i..i + window_size];
-        window_b = &measurements[i + 1..i + 1 + window_size];
-
-        let sum_a: usize = window_a.iter().sum();
-        let sum_b: usize = window_b.iter().sum();
-        if sum_b > sum_a {
-            count = count + 1;
+    let file = File::open("1.in").expect("Could not read input file.");
+    let format = env::args().nth(1);
+    if let Some(format) = format.as_deref() {
+        // `--report` prints depth statistics as a table, `--json` as json,
+        // `--sparkline` and `--svg` plot the depths, `--filter <filters>`
+        // counts after a comma separated chain of filters, e.g.
+        // `median:5:50,rate:100`
+        let input: Vec<u64> = BufReader::new(file)
+            .lines()
+            .map(|x| {
+                x.expect("Could not read input file.")
+                    .parse::<u64>()
+                    .expect("Could not parse value.")
+            })
+            .collect();
+        let report = || SonarReport::new(&input, &[1, 3], 10);
+        match format {
+            "--report" => print!("{}", report().table()),
+            "--json" => println!("{}", report().json()),
+            "--sparkline" => println!("{}", plot::sparkline(&input, 80)),
+            "--svg" => print!("{}", plot::svg(&input, &[3], 1000, 400)),
+            "--filter" => {
+                let filters: Vec<Filter> = env::args()
+                    .nth(2)
+                    .expect("Missing filters.")
+                    .split(',')
+                    .map(|f| f.parse().expect("Could not parse filter."))
+                    .collect();
+                let filtered = filter::apply(&input, &filters);
+                for rejection in &filtered.rejections {
+                    println!("{}", rejection);
+                }
+                println!("Part1: {:?}", count_depth_increases(&filtered.readings, 1));
+                println!("Part2: {:?}", count_depth_increases(&filtered.readings, 3));
+            }
+            _ => panic!("{}", format!("Unknown option: {:?}", format)),
         }
+        return Ok(());
     }
-    count
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    let mut sweep = Sweep::new(&[1, 3]);
+    sweep.read(BufReader::new(file))?;
+    let counts = sweep.counts();
+    println!("Part1: {:?}", counts[0].1);
+    println!("Part2: {:?}", counts[1].1);
 
-    #[test]
-    fn example_pt1() {
-        let input = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
-        assert_eq!(count_depth_increases(&input, 1), 7);
-    }
-
-    #[test]
-    fn example_pt2() {
-        let input = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
-        assert_eq!(count_depth_increases(&input, 3), 5);
-    }
+    Ok(())
 }
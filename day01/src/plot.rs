@@ -0,0 +1,140 @@
+use std::fmt::Write;
+
+use crate::{window_aggregates, Aggregator};
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const COLORS: [&str; 4] = ["#1f77b4", "#2ca02c", "#9467bd", "#8c564b"];
+const INCREASE_COLOR: &str = "#d62728";
+
+// one bar per group of measurements, averaged, at most `width` bars
+pub fn sparkline(measurements: &[u64], width: usize) -> String {
+    if measurements.is_empty() || width == 0 {
+        return String::new();
+    }
+    let group = measurements.len().div_ceil(width);
+    let averages: Vec<f64> = measurements
+        .chunks(group)
+        .map(|chunk| chunk.iter().sum::<u64>() as f64 / chunk.len() as f64)
+        .collect();
+    let (min, max) = bounds(&averages);
+    averages
+        .iter()
+        .map(|&v| {
+            let level = if max > min {
+                ((v - min) / (max - min) * (BARS.len() - 1) as f64).round() as usize
+            } else {
+                0
+            };
+            BARS[level]
+        })
+        .collect()
+}
+
+// line chart of the raw depths and of every window, drawn deeper towards the
+// bottom. windows are plotted as their sum divided by the window size so
+// they share the depth axis; segments that increase are drawn in red.
+pub fn svg(measurements: &[u64], window_sizes: &[usize], width: u32, height: u32) -> String {
+    let mut series = vec![(
+        "depth".to_string(),
+        measurements.iter().map(|&v| v as f64).collect(),
+    )];
+    for &size in window_sizes {
+        let label = format!("window {} (sum / {})", size, size);
+        series.push((
+            label,
+            window_aggregates(measurements, size, Aggregator::Mean),
+        ));
+    }
+    let all: Vec<f64> = series
+        .iter()
+        .flat_map(|(_, values)| values.clone())
+        .collect();
+    let (min, max) = bounds(&all);
+    let span = if max > min { max - min } else { 1.0 };
+    let steps = measurements.len().saturating_sub(1).max(1) as f64;
+    let x = |i: usize| i as f64 / steps * width as f64;
+    let y = |v: f64| (v - min) / span * height as f64;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\">",
+        width,
+        height + 20 * series.len() as u32,
+        width,
+        height + 20 * series.len() as u32
+    )
+    .unwrap();
+    writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+    for (idx, (label, values)) in series.iter().enumerate() {
+        let color = COLORS[idx % COLORS.len()];
+        // a window covers several measurements, center it on them
+        let offset = (measurements.len() - values.len()) as f64 / 2.0;
+        writeln!(out, "<g stroke-width=\"1\" fill=\"none\">").unwrap();
+        for (i, pair) in values.windows(2).enumerate() {
+            let stroke = if pair[1] > pair[0] {
+                INCREASE_COLOR
+            } else {
+                color
+            };
+            writeln!(
+                out,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\"/>",
+                x(i) + offset * x(1),
+                y(pair[0]),
+                x(i + 1) + offset * x(1),
+                y(pair[1]),
+                stroke
+            )
+            .unwrap();
+        }
+        writeln!(out, "</g>").unwrap();
+        writeln!(
+            out,
+            "<text x=\"4\" y=\"{}\" font-family=\"monospace\" font-size=\"12\" fill=\"{}\">{}</text>",
+            height + 15 + 20 * idx as u32,
+            color,
+            label
+        )
+        .unwrap();
+    }
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+fn bounds(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EXAMPLE;
+
+    #[test]
+    fn example_sparkline() {
+        assert_eq!(sparkline(&EXAMPLE, 10), "▁▁▂▂▁▂▅█▇▇");
+        assert_eq!(sparkline(&EXAMPLE, 5), "▁▂▁▇█");
+        assert_eq!(sparkline(&[5, 5, 5], 10), "▁▁▁");
+        assert_eq!(sparkline(&[], 10), "");
+    }
+
+    #[test]
+    fn example_svg() {
+        let svg = svg(&EXAMPLE, &[3], 90, 70);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // 9 segments for the depths and 7 for the window
+        assert_eq!(svg.matches("<line").count(), 16);
+        // 7 increases for the depths and 5 for the window
+        assert_eq!(svg.matches(INCREASE_COLOR).count(), 12);
+        assert!(svg.contains(">window 3 (sum / 3)</text>"));
+        // the deepest reading is at the bottom
+        assert!(svg.contains("x1=\"70.0\" y1=\"70.0\""));
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::window_deltas;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trend {
+    Increase,
+    Decrease,
+}
+
+impl Trend {
+    fn name(self) -> &'static str {
+        match self {
+            Trend::Increase => "increase",
+            Trend::Decrease => "decrease",
+        }
+    }
+}
+
+// consecutive windows whose sums strictly increase or decrease
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Run {
+    pub trend: Trend,
+    // index of the first measurement of the first window
+    pub start: usize,
+    // number of windows in the run
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowReport {
+    pub window_size: usize,
+    pub increases: usize,
+    pub decreases: usize,
+    pub flat: usize,
+    // the first of the longest runs, `None` if the sums never change
+    pub longest_run: Option<Run>,
+    pub max_rise: Option<u64>,
+    pub max_drop: Option<u64>,
+    // number of deltas per bucket, keyed by the bucket's lower bound
+    pub histogram: BTreeMap<i64, usize>,
+}
+
+impl WindowReport {
+    fn new(measurements: &[u64], window_size: usize, bucket_width: u64) -> Self {
+        let mut report = Self {
+            window_size,
+            increases: 0,
+            decreases: 0,
+            flat: 0,
+            longest_run: None,
+            max_rise: None,
+            max_drop: None,
+            histogram: BTreeMap::new(),
+        };
+        let mut current: Option<Run> = None;
+        for (idx, delta) in window_deltas(measurements, window_size).enumerate() {
+            let trend = match delta {
+                d if d > 0 => {
+                    report.increases += 1;
+                    report.max_rise = report.max_rise.max(Some(d.unsigned_abs()));
+                    Some(Trend::Increase)
+                }
+                d if d < 0 => {
+                    report.decreases += 1;
+                    report.max_drop = report.max_drop.max(Some(d.unsigned_abs()));
+                    Some(Trend::Decrease)
+                }
+                _ => {
+                    report.flat += 1;
+                    None
+                }
+            };
+            let bucket = delta.div_euclid(bucket_width as i64) * bucket_width as i64;
+            *report.histogram.entry(bucket).or_insert(0) += 1;
+
+            current = match (current, trend) {
+                (Some(run), Some(trend)) if run.trend == trend => Some(Run {
+                    length: run.length + 1,
+                    ..run
+                }),
+                (_, Some(trend)) => Some(Run {
+                    trend,
+                    start: idx,
+                    length: 2,
+                }),
+                (_, None) => None,
+            };
+            if let Some(run) = current {
+                if report
+                    .longest_run
+                    .is_none_or(|longest| run.length > longest.length)
+                {
+                    report.longest_run = Some(run);
+                }
+            }
+        }
+        report
+    }
+}
+
+// depth statistics for several window sizes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SonarReport {
+    pub windows: Vec<WindowReport>,
+    pub bucket_width: u64,
+}
+
+impl SonarReport {
+    pub fn new(measurements: &[u64], window_sizes: &[usize], bucket_width: u64) -> Self {
+        assert!(bucket_width > 0, "Bucket width must be positive.");
+        Self {
+            windows: window_sizes
+                .iter()
+                .map(|&size| WindowReport::new(measurements, size, bucket_width))
+                .collect(),
+            bucket_width,
+        }
+    }
+
+    pub fn table(&self) -> String {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{:>6} {:>9} {:>9} {:>6} {:>24} {:>8} {:>8}",
+            "window", "increases", "decreases", "flat", "longest run", "max rise", "max drop"
+        )
+        .unwrap();
+        for w in &self.windows {
+            let run = match w.longest_run {
+                Some(run) => format!("{} x{} from {}", run.trend.name(), run.length, run.start),
+                None => "-".to_string(),
+            };
+            let or_dash = |v: Option<u64>| v.map_or("-".to_string(), |v| v.to_string());
+            writeln!(
+                out,
+                "{:>6} {:>9} {:>9} {:>6} {:>24} {:>8} {:>8}",
+                w.window_size,
+                w.increases,
+                w.decreases,
+                w.flat,
+                run,
+                or_dash(w.max_rise),
+                or_dash(w.max_drop)
+            )
+            .unwrap();
+        }
+        for w in &self.windows {
+            writeln!(out, "\ndeltas for window {}:", w.window_size).unwrap();
+            for (bucket, count) in &w.histogram {
+                let range = format!("{}..{}", bucket, bucket + self.bucket_width as i64);
+                writeln!(out, "{:>14} {:>6}", range, count).unwrap();
+            }
+        }
+        out
+    }
+
+    pub fn json(&self) -> String {
+        let or_null = |v: Option<u64>| v.map_or("null".to_string(), |v| v.to_string());
+        let windows: Vec<String> = self
+            .windows
+            .iter()
+            .map(|w| {
+                let run = match w.longest_run {
+                    Some(run) => format!(
+                        "{{\"trend\":\"{}\",\"start\":{},\"length\":{}}}",
+                        run.trend.name(),
+                        run.start,
+                        run.length
+                    ),
+                    None => "null".to_string(),
+                };
+                let histogram: Vec<String> = w
+                    .histogram
+                    .iter()
+                    .map(|(bucket, count)| format!("{{\"from\":{},\"count\":{}}}", bucket, count))
+                    .collect();
+                format!(
+                    "{{\"window_size\":{},\"increases\":{},\"decreases\":{},\"flat\":{},\
+                     \"longest_run\":{},\"max_rise\":{},\"max_drop\":{},\"histogram\":[{}]}}",
+                    w.window_size,
+                    w.increases,
+                    w.decreases,
+                    w.flat,
+                    run,
+                    or_null(w.max_rise),
+                    or_null(w.max_drop),
+                    histogram.join(",")
+                )
+            })
+            .collect();
+        format!(
+            "{{\"bucket_width\":{},\"windows\":[{}]}}",
+            self.bucket_width,
+            windows.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_depth_increases, EXAMPLE};
+
+    #[test]
+    fn example_report() {
+        let input = EXAMPLE;
+        let report = SonarReport::new(&input, &[1, 3], 10);
+        let w1 = &report.windows[0];
+        assert_eq!(w1.increases, count_depth_increases(&input, 1));
+        assert_eq!((w1.increases, w1.decreases, w1.flat), (7, 2, 0));
+        assert_eq!(
+            w1.longest_run,
+            Some(Run {
+                trend: Trend::Increase,
+                start: 0,
+                length: 4
+            })
+        );
+        assert_eq!(w1.max_rise, Some(33));
+        assert_eq!(w1.max_drop, Some(10));
+        let histogram: Vec<(i64, usize)> = w1.histogram.iter().map(|(&b, &c)| (b, c)).collect();
+        assert_eq!(histogram, vec![(-10, 2), (0, 5), (20, 1), (30, 1)]);
+
+        // window sums 607 618 618 617 647 716 769 792
+        let w3 = &report.windows[1];
+        assert_eq!(w3.increases, count_depth_increases(&input, 3));
+        assert_eq!((w3.increases, w3.decreases, w3.flat), (5, 1, 1));
+        assert_eq!(
+            w3.longest_run,
+            Some(Run {
+                trend: Trend::Increase,
+                start: 3,
+                length: 5
+            })
+        );
+        assert_eq!(w3.max_rise, Some(69));
+        assert_eq!(w3.max_drop, Some(1));
+    }
+
+    #[test]
+    fn flat_and_short() {
+        let report = SonarReport::new(&[5, 5, 5], &[1, 4], 1);
+        assert_eq!(report.windows[0].flat, 2);
+        assert_eq!(report.windows[0].longest_run, None);
+        assert_eq!(report.windows[0].max_rise, None);
+        assert_eq!(report.windows[1].flat, 0);
+        assert!(report.windows[1].histogram.is_empty());
+    }
+
+    #[test]
+    fn json_output() {
+        let report = SonarReport::new(&[3, 1, 1, 4], &[1], 5);
+        assert_eq!(
+            report.json(),
+            "{\"bucket_width\":5,\"windows\":[{\"window_size\":1,\"increases\":1,\
+             \"decreases\":1,\"flat\":1,\"longest_run\":{\"trend\":\"decrease\",\
+             \"start\":0,\"length\":2},\"max_rise\":3,\"max_drop\":2,\"histogram\":\
+             [{\"from\":-5,\"count\":1},{\"from\":0,\"count\":2}]}]}"
+        );
+    }
+
+    #[test]
+    fn table_output() {
+        let table = SonarReport::new(&EXAMPLE, &[1, 3], 10).table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].trim_start().starts_with("window"));
+        assert!(lines[1].contains("increase x4 from 0"));
+        assert!(lines[2].contains("increase x5 from 3"));
+        assert!(table.contains("deltas for window 3:"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::prelude::*;
+
+// counts depth increases for several window sizes in a single pass over the
+// measurements. only the last `max(window_sizes)` measurements are kept, so
+// logs of any length can be analysed.
+#[derive(Debug)]
+pub struct Sweep {
+    window_sizes: Vec<usize>,
+    counts: Vec<usize>,
+    recent: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl Sweep {
+    pub fn new(window_sizes: &[usize]) -> Self {
+        let capacity = window_sizes.iter().copied().max().unwrap_or(0);
+        Self {
+            window_sizes: window_sizes.to_vec(),
+            counts: vec![0; window_sizes.len()],
+            recent: VecDeque::with_capacity(capacity + 1),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, depth: u64) {
+        for (&size, count) in self.window_sizes.iter().zip(self.counts.iter_mut()) {
+            // see `count_depth_increases`, only the measurements leaving and
+            // entering the window matter
+            if size > 0
+                && self.recent.len() >= size
+                && depth > self.recent[self.recent.len() - size]
+            {
+                *count += 1;
+            }
+        }
+        self.recent.push_back(depth);
+        if self.recent.len() > self.capacity {
+            self.recent.pop_front();
+        }
+    }
+
+    pub fn consume<I>(&mut self, depths: I)
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        for depth in depths {
+            self.push(depth);
+        }
+    }
+
+    // one measurement per line, blank lines are skipped
+    pub fn read<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let depth = line.parse::<u64>().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Could not parse value: {:?}", line),
+                )
+            })?;
+            self.push(depth);
+        }
+        Ok(())
+    }
+
+    // number of increases for each window size, in the order given to `new`
+    pub fn counts(&self) -> Vec<(usize, usize)> {
+        self.window_sizes
+            .iter()
+            .copied()
+            .zip(self.counts.iter().copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_depth_increases, EXAMPLE};
+
+    #[test]
+    fn example() {
+        let mut sweep = Sweep::new(&[1, 3]);
+        sweep.consume(EXAMPLE);
+        assert_eq!(sweep.counts(), vec![(1, 7), (3, 5)]);
+    }
+
+    #[test]
+    fn matches_count_depth_increases() {
+        let input: Vec<u64> = (0..500u64).map(|i| (i * 7919) % 211).collect();
+        let sizes = [0, 1, 2, 3, 10, 600];
+        let mut sweep = Sweep::new(&sizes);
+        sweep.consume(input.iter().copied());
+        for (size, count) in sweep.counts() {
+            assert_eq!(count, count_depth_increases(&input, size));
+        }
+    }
+
+    #[test]
+    fn read_lines() {
+        let input = "199\n200\n208\n210\n\n200\n207\n240\n269\n260\n263\n";
+        let mut sweep = Sweep::new(&[3, 1]);
+        sweep.read(input.as_bytes()).unwrap();
+        assert_eq!(sweep.counts(), vec![(3, 5), (1, 7)]);
+
+        let err = Sweep::new(&[1]).read("1\nx\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
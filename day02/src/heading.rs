@@ -0,0 +1,177 @@
+use crate::{run, Instruction, MoveError, MovementModel, Submarine, Surface};
+
+// a sub that can turn. x and z make up the horizontal plane, the heading is
+// in degrees with 0 along x like the 2-D models and turning right towards z.
+// only the horizontal position is fractional, depth follows the aim as in
+// part 2.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Submarine3d {
+    pub x: f64,
+    pub z: f64,
+    pub y: i64,
+    pub aim: i64,
+    // in 0..360
+    pub heading: u32,
+    pub surface: Surface,
+}
+
+// the aim model plus turning, forward and back move along the heading
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Heading;
+
+impl MovementModel<Submarine3d> for Heading {
+    fn step(
+        &self,
+        sub: &Submarine3d,
+        instruction: Instruction,
+        step: usize,
+    ) -> Result<Submarine3d, MoveError> {
+        let overflow = MoveError::Overflow { step };
+        let mut next = *sub;
+        let units = match instruction {
+            Instruction::Down(unit) => {
+                next.aim = sub.aim.checked_add(unit.into()).ok_or(overflow)?;
+                return Ok(next);
+            }
+            Instruction::Up(unit) => {
+                next.aim = sub.aim.checked_sub(unit.into()).ok_or(overflow)?;
+                return Ok(next);
+            }
+            Instruction::Left(degrees) => {
+                next.heading = (sub.heading + 360 - degrees % 360) % 360;
+                return Ok(next);
+            }
+            Instruction::Right(degrees) => {
+                next.heading = (sub.heading + degrees % 360) % 360;
+                return Ok(next);
+            }
+            Instruction::Forward(units) => i64::from(units),
+            Instruction::Back(units) => -i64::from(units),
+        };
+        let depth = sub
+            .aim
+            .checked_mul(units)
+            .and_then(|dive| sub.y.checked_add(dive))
+            .ok_or(overflow)?;
+        let (dx, dz) = direction(sub.heading);
+        next.x = sub.x + units as f64 * dx;
+        next.z = sub.z + units as f64 * dz;
+        next.y = sub.surface.apply(step, depth)?;
+        Ok(next)
+    }
+}
+
+// unit vector along a heading, exact for the right angles
+fn direction(heading: u32) -> (f64, f64) {
+    match heading {
+        0 => (1.0, 0.0),
+        90 => (0.0, 1.0),
+        180 => (-1.0, 0.0),
+        270 => (0.0, -1.0),
+        _ => {
+            let radians = f64::from(heading).to_radians();
+            (radians.cos(), radians.sin())
+        }
+    }
+}
+
+// the same position, facing along x
+impl From<Submarine> for Submarine3d {
+    fn from(sub: Submarine) -> Self {
+        Self {
+            x: sub.x as f64,
+            z: 0.0,
+            y: sub.y,
+            aim: sub.aim,
+            heading: 0,
+            surface: sub.surface,
+        }
+    }
+}
+
+impl Submarine3d {
+    pub fn with_surface(surface: Surface) -> Self {
+        Self {
+            surface,
+            ..Default::default()
+        }
+    }
+
+    // `Submarine::run` for the 3-D sub
+    pub fn run<M>(&mut self, instructions: &[Instruction], model: &M) -> Result<(), MoveError>
+    where
+        M: MovementModel<Submarine3d> + ?Sized,
+    {
+        run(self, model, instructions.iter().copied(), 0, |_, _, _| {})
+    }
+
+    // distance from the start in the horizontal plane
+    pub fn range(&self) -> f64 {
+        self.x.hypot(self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_instructions, Aim, Direct, EXAMPLE};
+
+    #[test]
+    fn zero_turns_is_part2() {
+        let inputs = [
+            EXAMPLE,
+            "down 3\nforward 4\nup 5\nback 2\nforward 7\nup 9\nforward 1",
+            // a full circle is no turn at all
+            "down 2\nright 90\nright 180\nleft 630\nforward 6\nright 360\nforward 1",
+        ];
+        for input in inputs {
+            let instructions = parse_instructions(input);
+            let flat: Vec<Instruction> = instructions
+                .iter()
+                .copied()
+                .filter(|i| !matches!(i, Instruction::Left(_) | Instruction::Right(_)))
+                .collect();
+            for surface in [Surface::Error, Surface::Clamp] {
+                let mut sub = Submarine::with_surface(surface);
+                let expected = sub.run(&flat, &Aim).map(|_| Submarine3d::from(sub));
+
+                let mut sub = Submarine3d::with_surface(surface);
+                let result = sub.run(&instructions, &Heading).map(|_| sub);
+                assert_eq!(result, expected, "{:?}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn turns() {
+        let mut sub = Submarine3d::default();
+        sub.run(&parse_instructions("down 2\nright 90\nforward 3"), &Heading)
+            .unwrap();
+        assert_eq!((sub.x, sub.z, sub.y, sub.heading), (0.0, 3.0, 6, 90));
+
+        sub.run(&parse_instructions("left 180\nforward 5\nup 2"), &Heading)
+            .unwrap();
+        assert_eq!((sub.x, sub.z, sub.y, sub.heading), (0.0, -2.0, 16, 270));
+
+        let mut sub = Submarine3d::default();
+        sub.run(&parse_instructions("left 45\nforward 10"), &Heading)
+            .unwrap();
+        assert_eq!(sub.heading, 315);
+        assert!((sub.x - 7.071).abs() < 1e-3);
+        assert!((sub.z + 7.071).abs() < 1e-3);
+        assert!((sub.range() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flat_models_cannot_turn() {
+        let instructions = parse_instructions("forward 2\nleft 90\nforward 1");
+        let mut sub = Submarine::default();
+        let err = sub.run(&instructions, &Direct).unwrap_err();
+        assert_eq!(err, MoveError::Turn { step: 1 });
+        assert_eq!(
+            err.to_string(),
+            "instruction 1 turns, which needs the 3-D model"
+        );
+        assert_eq!(sub.x, 2);
+    }
+}
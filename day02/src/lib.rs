@@ -0,0 +1,453 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub mod heading;
+pub mod plan;
+pub mod repl;
+pub mod script;
+pub mod serial;
+pub mod trajectory;
+
+use trajectory::Trajectory;
+
+// what happens when an instruction would take the sub above the surface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Surface {
+    // stop with `MoveError::AboveSurface`
+    #[default]
+    Error,
+    // stay at depth 0
+    Clamp,
+}
+
+impl Surface {
+    // apply the policy to a new depth
+    pub fn apply(self, step: usize, depth: i64) -> Result<i64, MoveError> {
+        match (depth < 0, self) {
+            (false, _) => Ok(depth),
+            (true, Surface::Clamp) => Ok(0),
+            (true, Surface::Error) => Err(MoveError::AboveSurface { step, depth }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Submarine {
+    pub x: i64,
+    pub y: i64,
+    pub aim: i64,
+    pub surface: Surface,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Instruction {
+    Down(u32),
+    Up(u32),
+    Forward(u32),
+    Back(u32),
+    // turns in degrees, only the 3-D model in `heading` follows them
+    Left(u32),
+    Right(u32),
+}
+
+impl Instruction {
+    pub fn new(direction: &str, units: u32) -> Option<Self> {
+        match direction {
+            "forward" => Some(Instruction::Forward(units)),
+            "down" => Some(Instruction::Down(units)),
+            "up" => Some(Instruction::Up(units)),
+            "back" => Some(Instruction::Back(units)),
+            "left" => Some(Instruction::Left(units)),
+            "right" => Some(Instruction::Right(units)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Down(units) => write!(f, "down {}", units),
+            Instruction::Up(units) => write!(f, "up {}", units),
+            Instruction::Forward(units) => write!(f, "forward {}", units),
+            Instruction::Back(units) => write!(f, "back {}", units),
+            Instruction::Left(degrees) => write!(f, "left {}", degrees),
+            Instruction::Right(degrees) => write!(f, "right {}", degrees),
+        }
+    }
+}
+
+// an instruction that could not be carried out, `step` is its index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveError {
+    AboveSurface { step: usize, depth: i64 },
+    Overflow { step: usize },
+    // a turn under a model without a heading
+    Turn { step: usize },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::AboveSurface { step, depth } => write!(
+                f,
+                "instruction {} would take the sub above the surface to depth {}",
+                step, depth
+            ),
+            MoveError::Overflow { step } => {
+                write!(f, "instruction {} overflows the position", step)
+            }
+            MoveError::Turn { step } => {
+                write!(f, "instruction {} turns, which needs the 3-D model", step)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+// how a single instruction moves the sub. models return the new state
+// rather than changing the sub, so a failed instruction leaves it as it was.
+pub trait MovementModel<S = Submarine> {
+    fn step(&self, sub: &S, instruction: Instruction, step: usize) -> Result<S, MoveError>;
+}
+
+// part 1: up and down change the depth directly
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Direct;
+
+// part 2: up and down change the aim, forward dives along it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Aim;
+
+// another model plus a current that moves the sub by `x` and `y` during
+// every instruction
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Drift<M> {
+    pub model: M,
+    pub x: i64,
+    pub y: i64,
+}
+
+impl MovementModel for Direct {
+    fn step(
+        &self,
+        sub: &Submarine,
+        instruction: Instruction,
+        step: usize,
+    ) -> Result<Submarine, MoveError> {
+        let overflow = MoveError::Overflow { step };
+        let mut next = *sub;
+        match instruction {
+            Instruction::Down(unit) => {
+                next.y = sub.y.checked_add(unit.into()).ok_or(overflow)?;
+            }
+            Instruction::Up(unit) => {
+                let depth = sub.y.checked_sub(unit.into()).ok_or(overflow)?;
+                next.y = sub.surface(step, depth)?;
+            }
+            Instruction::Forward(unit) => {
+                next.x = sub.x.checked_add(unit.into()).ok_or(overflow)?;
+            }
+            Instruction::Back(unit) => {
+                next.x = sub.x.checked_sub(unit.into()).ok_or(overflow)?;
+            }
+            Instruction::Left(_) | Instruction::Right(_) => return Err(MoveError::Turn { step }),
+        }
+        Ok(next)
+    }
+}
+
+impl MovementModel for Aim {
+    fn step(
+        &self,
+        sub: &Submarine,
+        instruction: Instruction,
+        step: usize,
+    ) -> Result<Submarine, MoveError> {
+        let overflow = MoveError::Overflow { step };
+        let mut next = *sub;
+        match instruction {
+            Instruction::Down(unit) => {
+                next.aim = sub.aim.checked_add(unit.into()).ok_or(overflow)?;
+            }
+            Instruction::Up(unit) => {
+                next.aim = sub.aim.checked_sub(unit.into()).ok_or(overflow)?;
+            }
+            Instruction::Forward(units) => {
+                let depth = sub
+                    .aim
+                    .checked_mul(units.into())
+                    .and_then(|dive| sub.y.checked_add(dive))
+                    .ok_or(overflow)?;
+                next.x = sub.x.checked_add(units.into()).ok_or(overflow)?;
+                next.y = sub.surface(step, depth)?;
+            }
+            // retrace a forward move
+            Instruction::Back(units) => {
+                let depth = sub
+                    .aim
+                    .checked_mul(units.into())
+                    .and_then(|dive| sub.y.checked_sub(dive))
+                    .ok_or(overflow)?;
+                next.x = sub.x.checked_sub(units.into()).ok_or(overflow)?;
+                next.y = sub.surface(step, depth)?;
+            }
+            Instruction::Left(_) | Instruction::Right(_) => return Err(MoveError::Turn { step }),
+        };
+        Ok(next)
+    }
+}
+
+impl<M: MovementModel> MovementModel for Drift<M> {
+    fn step(
+        &self,
+        sub: &Submarine,
+        instruction: Instruction,
+        step: usize,
+    ) -> Result<Submarine, MoveError> {
+        let overflow = MoveError::Overflow { step };
+        let mut next = self.model.step(sub, instruction, step)?;
+        next.x = next.x.checked_add(self.x).ok_or(overflow)?;
+        let depth = next.y.checked_add(self.y).ok_or(overflow)?;
+        next.y = next.surface(step, depth)?;
+        Ok(next)
+    }
+}
+
+// the interpreter loop for every model and kind of sub. instructions are
+// numbered from `first_step`, and `visit` gets the step, the instruction and
+// the sub after every one that succeeded. a failed instruction leaves the
+// sub as it was before it.
+pub fn run<S, M, I, F>(
+    sub: &mut S,
+    model: &M,
+    instructions: I,
+    first_step: usize,
+    mut visit: F,
+) -> Result<(), MoveError>
+where
+    M: MovementModel<S> + ?Sized,
+    I: IntoIterator<Item = Instruction>,
+    F: FnMut(usize, Instruction, &S),
+{
+    for (step, instruction) in (first_step..).zip(instructions) {
+        *sub = model.step(sub, instruction, step)?;
+        visit(step, instruction, sub);
+    }
+    Ok(())
+}
+
+impl Submarine {
+    pub fn with_surface(surface: Surface) -> Self {
+        Self {
+            surface,
+            ..Default::default()
+        }
+    }
+
+    // follow the instructions under the given model, stopping at the first
+    // one that fails
+    pub fn run<M>(&mut self, instructions: &[Instruction], model: &M) -> Result<(), MoveError>
+    where
+        M: MovementModel + ?Sized,
+    {
+        run(self, model, instructions.iter().copied(), 0, |_, _, _| {})
+    }
+
+    // like `run`, adding the starting position and the position after every
+    // instruction that succeeded to the trajectory
+    pub fn record<M>(
+        &mut self,
+        instructions: &[Instruction],
+        model: &M,
+        trajectory: &mut Trajectory,
+    ) -> Result<(), MoveError>
+    where
+        M: MovementModel + ?Sized,
+    {
+        trajectory.push(None, self);
+        run(
+            self,
+            model,
+            instructions.iter().copied(),
+            0,
+            |_, instruction, sub| trajectory.push(Some(instruction), sub),
+        )
+    }
+
+    pub fn interpret(&mut self, instructions: &[Instruction]) -> Result<(), MoveError> {
+        self.run(instructions, &Direct)
+    }
+
+    pub fn interpret_pt2(&mut self, instructions: &[Instruction]) -> Result<(), MoveError> {
+        self.run(instructions, &Aim)
+    }
+
+    // horizontal position times depth, `None` on overflow
+    pub fn product(&self) -> Option<i64> {
+        self.x.checked_mul(self.y)
+    }
+
+    // apply the surface policy to a new depth
+    pub fn surface(&self, step: usize, depth: i64) -> Result<i64, MoveError> {
+        self.surface.apply(step, depth)
+    }
+}
+
+// a single line of the puzzle input, like `forward 5`
+pub fn parse_instruction(line: &str) -> Result<Instruction, String> {
+    let (direction, units) = line
+        .split_whitespace()
+        .collect_tuple()
+        .ok_or_else(|| format!("Expected <direction> <units>, found {:?}", line))?;
+    let units: u32 = units
+        .parse()
+        .map_err(|_| format!("Could not parse units: {:?}", units))?;
+    Instruction::new(direction, units).ok_or_else(|| format!("Unknown direction: {:?}", direction))
+}
+
+pub fn parse_instructions(input: &str) -> Vec<Instruction> {
+    input
+        .lines()
+        .map(|line| parse_instruction(line).unwrap_or_else(|err| panic!("{}", err)))
+        .collect()
+}
+
+// the instructions of the puzzle's example
+#[cfg(test)]
+pub(crate) const EXAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn example_pt1() {
+        let mut sub = Submarine::default();
+
+        let instructions: Vec<Instruction> = parse_instructions(EXAMPLE);
+        sub.interpret(&instructions).unwrap();
+        assert_eq!(sub.x * sub.y, 150);
+    }
+
+    #[test]
+    fn example_pt2() {
+        let mut sub = Submarine::default();
+
+        let instructions: Vec<Instruction> = parse_instructions(EXAMPLE);
+        sub.interpret_pt2(&instructions).unwrap();
+        assert_eq!(sub.x * sub.y, 900);
+    }
+
+    #[test]
+    fn above_surface() {
+        let instructions = parse_instructions("down 2\nforward 1\nup 5\nforward 3");
+
+        let mut sub = Submarine::default();
+        let err = sub.interpret(&instructions).unwrap_err();
+        assert_eq!(err, MoveError::AboveSurface { step: 2, depth: -3 });
+        assert_eq!((sub.x, sub.y), (1, 2));
+
+        let mut sub = Submarine::with_surface(Surface::Clamp);
+        sub.interpret(&instructions).unwrap();
+        assert_eq!((sub.x, sub.y), (4, 0));
+
+        // negative aim is fine until it is used to climb out of the water
+        let mut sub = Submarine::default();
+        let err = sub.interpret_pt2(&instructions).unwrap_err();
+        assert_eq!(err, MoveError::AboveSurface { step: 3, depth: -7 });
+        assert_eq!((sub.x, sub.y, sub.aim), (1, 2, -3));
+
+        let mut sub = Submarine::with_surface(Surface::Clamp);
+        sub.interpret_pt2(&instructions).unwrap();
+        assert_eq!((sub.x, sub.y, sub.aim), (4, 0, -3));
+    }
+
+    #[test]
+    fn overflow() {
+        let instructions = vec![Instruction::Down(u32::MAX); 3]
+            .into_iter()
+            .chain(vec![Instruction::Forward(u32::MAX); 3])
+            .collect::<Vec<_>>();
+        let mut sub = Submarine::default();
+        let err = sub.interpret_pt2(&instructions).unwrap_err();
+        assert_eq!(err, MoveError::Overflow { step: 3 });
+        assert_eq!(err.to_string(), "instruction 3 overflows the position");
+    }
+
+    #[test]
+    fn models() {
+        let instructions = parse_instructions(EXAMPLE);
+        let models: [(&dyn MovementModel, i64); 2] = [(&Direct, 150), (&Aim, 900)];
+        for (model, product) in models {
+            let mut sub = Submarine::default();
+            sub.run(&instructions, model).unwrap();
+            assert_eq!(sub.product(), Some(product));
+        }
+    }
+
+    #[test]
+    fn back() {
+        let instructions = vec![
+            Instruction::Down(2),
+            Instruction::Forward(5),
+            Instruction::Back(3),
+        ];
+        let mut sub = Submarine::default();
+        sub.run(&instructions, &Direct).unwrap();
+        assert_eq!((sub.x, sub.y), (2, 2));
+
+        // under the aim model, back retraces the dive
+        let mut sub = Submarine::default();
+        sub.run(&instructions, &Aim).unwrap();
+        assert_eq!((sub.x, sub.y, sub.aim), (2, 4, 2));
+
+        let mut sub = Submarine::default();
+        let err = sub
+            .run(&[Instruction::Down(1), Instruction::Back(1)], &Aim)
+            .unwrap_err();
+        assert_eq!(err, MoveError::AboveSurface { step: 1, depth: -1 });
+        assert_eq!(Instruction::Back(3).to_string(), "back 3");
+        assert_eq!(Instruction::new("sideways", 3), None);
+    }
+
+    #[test]
+    fn drift() {
+        let instructions = parse_instructions(EXAMPLE);
+
+        // no current is the same as the model itself
+        let mut sub = Submarine::default();
+        let still = Drift {
+            model: Aim,
+            x: 0,
+            y: 0,
+        };
+        sub.run(&instructions, &still).unwrap();
+        assert_eq!(sub.product(), Some(900));
+
+        let mut sub = Submarine::default();
+        let current = Drift {
+            model: Direct,
+            x: -1,
+            y: 2,
+        };
+        sub.run(&instructions, &current).unwrap();
+        assert_eq!((sub.x, sub.y), (9, 22));
+
+        // an upward current pushes the sub out of the water
+        let mut sub = Submarine::default();
+        let current = Drift {
+            model: Direct,
+            x: 0,
+            y: -1,
+        };
+        let err = sub.run(&instructions, &current).unwrap_err();
+        assert_eq!(err, MoveError::AboveSurface { step: 0, depth: -1 });
+        assert_eq!(sub, Submarine::default());
+    }
+}
@@ -1,6 +1,14 @@
-use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
+use day02::plan::{plan, Limits, Objective};
+use day02::repl::repl;
+use day02::script::parse_script;
+use day02::serial::{
+    instructions_from_json, Checkpoint, Fingerprint, FormatError, InstructionReader,
+};
+use day02::trajectory::Trajectory;
+use day02::{parse_instructions, Aim, Direct, Instruction, Submarine};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, prelude::*, BufReader};
 use std::path::Path;
 
 fn read<P>(path: P) -> std::io::Result<String>
@@ -13,110 +21,176 @@ where
     Ok(contents)
 }
 
-fn main() -> std::io::Result<()> {
-    let contents = read("2.in").expect("Could not read input file.");
-    let instructions = parse_instructions(&contents);
-    {
-        let mut sub = Submarine { x: 0, y: 0, aim: 0 };
-        sub.interpret(&instructions);
-        println!("part1: {:?}", sub.x * sub.y);
+// `plan <x> <depth> [--units] [--max-aim N] [--max-depth N]` prints puzzle
+// input that takes the part 2 sub to the target
+fn print_plan(args: &[String]) {
+    let number = |idx: usize| -> u64 {
+        let arg = args.get(idx).expect("Missing number.");
+        arg.parse()
+            .unwrap_or_else(|_| panic!("{}", format!("Could not parse number: {:?}", arg)))
+    };
+    let x = u32::try_from(number(0)).expect("Horizontal position too large.");
+    let depth = number(1);
+    let mut objective = Objective::Instructions;
+    let mut limits = Limits::default();
+    let mut idx = 2;
+    while idx < args.len() {
+        match args[idx].as_str() {
+            "--units" => objective = Objective::Units,
+            "--max-aim" => {
+                idx += 1;
+                limits.max_aim = Some(u32::try_from(number(idx)).expect("Aim too large."));
+            }
+            "--max-depth" => {
+                idx += 1;
+                limits.max_depth = Some(number(idx));
+            }
+            other => panic!("{}", format!("Unknown option: {:?}", other)),
+        }
+        idx += 1;
     }
-    {
-        let mut sub = Submarine { x: 0, y: 0, aim: 0 };
-        sub.interpret_pt2(&instructions);
-        println!("part2: {:?}", sub.x * sub.y);
+    let instructions = plan(x, depth, objective, limits).unwrap_or_else(|err| panic!("{}", err));
+    for instruction in instructions {
+        println!("{}", instruction);
     }
-
-    Ok(())
 }
 
-struct Submarine {
-    x: u32,
-    y: u32,
-    aim: u32,
+// remove `name <value>` from the arguments
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let idx = args.iter().position(|arg| arg == name)?;
+    let value = args
+        .get(idx + 1)
+        .unwrap_or_else(|| panic!("{}", format!("Missing value for {}", name)))
+        .clone();
+    args.drain(idx..idx + 2);
+    Some(value)
 }
 
-enum Instruction {
-    Down(u32),
-    Up(u32),
-    Forward(u32),
-}
-
-impl Submarine {
-    fn interpret(&mut self, instructions: &Vec<Instruction>) {
-        for instruction in instructions {
-            match instruction {
-                Instruction::Down(unit) => self.y = self.y + unit,
-                Instruction::Up(unit) => self.y = self.y - unit,
-                Instruction::Forward(unit) => self.x = self.x + unit,
-            }
-        }
-    }
+type Instructions = Box<dyn Iterator<Item = Result<Instruction, FormatError>>>;
 
-    fn interpret_pt2(&mut self, instructions: &Vec<Instruction>) {
-        for instruction in instructions {
-            match instruction {
-                Instruction::Down(unit) => self.aim = self.aim + unit,
-                Instruction::Up(unit) => self.aim = self.aim - unit,
-                Instruction::Forward(units) => {
-                    self.x = self.x + units;
-                    self.y = self.y + (self.aim * units);
-                }
-            };
-        }
+// a JSON instruction file is read at once, a binary one streamed
+fn read_instructions(path: &str) -> Result<Instructions, FormatError> {
+    if path.ends_with(".json") {
+        let instructions = instructions_from_json(&read(path)?)?;
+        Ok(Box::new(instructions.into_iter().map(Ok)))
+    } else {
+        let reader = InstructionReader::new(BufReader::new(File::open(path)?));
+        Ok(Box::new(reader))
     }
 }
 
-fn parse_instructions(input: &str) -> Vec<Instruction> {
-    input
-        .lines()
-        .map(|line| {
-            let (direction, units) = line.splitn(2, ' ').collect_tuple().unwrap();
-            let units: u32 = units.parse().expect("Could not parse units.");
-            match direction {
-                "forward" => Instruction::Forward(units),
-                "down" => Instruction::Down(units),
-                "up" => Instruction::Up(units),
-                _ => panic!("{}", format!("Unkown direction: {:?}", direction)),
-            }
-        })
-        .collect()
+// a checkpoint of a run over the instructions in `input`
+fn load_checkpoint(
+    path: &str,
+    bytes: &[u8],
+    input: Fingerprint,
+) -> Result<Checkpoint, FormatError> {
+    let checkpoint = if path.ends_with(".json") {
+        Checkpoint::from_json(&String::from_utf8_lossy(bytes))?
+    } else {
+        Checkpoint::from_bytes(bytes)?
+    };
+    checkpoint.check_input(input)?;
+    Ok(checkpoint)
 }
 
-#[cfg(test)]
-mod tests {
-
-    use super::*;
-
-    #[test]
-    fn example_pt1() {
-        let input = "forward 5\n\
-                     down 5\n\
-                     forward 8\n\
-                     up 3\n\
-                     down 8\n\
-                     forward 2";
-
-        let mut sub = Submarine { x: 0, y: 0, aim: 0 };
+fn main() -> std::io::Result<()> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
 
-        let instructions: Vec<Instruction> = parse_instructions(&input);
-        sub.interpret(&instructions);
-        assert_eq!(sub.x * sub.y, 150);
+    if args.first().map(String::as_str) == Some("repl") {
+        let stdin = io::stdin();
+        return repl(stdin.lock(), io::stdout());
+    }
+    if args.first().map(String::as_str) == Some("plan") {
+        print_plan(&args[1..]);
+        return Ok(());
     }
 
-    #[test]
-    fn example_pt2() {
-        let input = "forward 5\n\
-                     down 5\n\
-                     forward 8\n\
-                     up 3\n\
-                     down 8\n\
-                     forward 2";
-
-        let mut sub = Submarine { x: 0, y: 0, aim: 0 };
+    // `--script <path>` runs a navigation script instead of the puzzle input,
+    // `--instructions <path>` a JSON (`.json`) or binary instruction file
+    let (path, instructions): (String, Instructions) =
+        if let Some(path) = take_option(&mut args, "--script") {
+            let contents = read(&path).expect("Could not read script file.");
+            let instructions =
+                parse_script(&contents).unwrap_or_else(|err| panic!("{}: {}", path, err));
+            (path, Box::new(instructions.into_iter().map(Ok)))
+        } else if let Some(path) = take_option(&mut args, "--instructions") {
+            let instructions =
+                read_instructions(&path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+            (path, instructions)
+        } else {
+            let contents = read("2.in").expect("Could not read input file.");
+            let instructions = parse_instructions(&contents);
+            (
+                "2.in".to_string(),
+                Box::new(instructions.into_iter().map(Ok)),
+            )
+        };
+
+    // `--checkpoint <path>` runs part 2 from the checkpoint, if there is one,
+    // and saves it every 100000 instructions. a checkpoint only resumes the
+    // input it was taken from, and binary instruction files are streamed.
+    if let Some(checkpoint_path) = take_option(&mut args, "--checkpoint") {
+        let input = Fingerprint::of(File::open(&path)?)?;
+        let mut checkpoint = match fs::read(&checkpoint_path) {
+            Ok(bytes) => load_checkpoint(&checkpoint_path, &bytes, input)
+                .unwrap_or_else(|err| panic!("{}: {}", checkpoint_path, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Checkpoint::new(input),
+            Err(err) => return Err(err),
+        };
+        let save = |checkpoint: &Checkpoint| {
+            let bytes = if checkpoint_path.ends_with(".json") {
+                checkpoint.to_json().into_bytes()
+            } else {
+                checkpoint.to_bytes()
+            };
+            fs::write(&checkpoint_path, bytes).expect("Could not save checkpoint.");
+        };
+        let result = checkpoint.resume(instructions, &Aim, 100_000, save);
+        save(&checkpoint);
+        result.unwrap_or_else(|err| panic!("{}: {}", path, err));
+        println!(
+            "part2: {:?}",
+            checkpoint.sub.product().expect("Product overflows.")
+        );
+        return Ok(());
+    }
+    let instructions: Vec<Instruction> = instructions
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err| panic!("{}: {}", path, err));
+
+    // `--csv` or `--svg` export the part 2 trajectory instead
+    if let Some(format) = args.first() {
+        let mut sub = Submarine::default();
+        let mut trajectory = Trajectory::default();
+        sub.record(&instructions, &Aim, &mut trajectory)
+            .expect("Could not follow instructions.");
+        match format.as_str() {
+            "--csv" => print!("{}", trajectory.to_csv()),
+            "--svg" => print!("{}", trajectory.to_svg(1000, 400)),
+            _ => panic!("{}", format!("Unknown option: {:?}", format)),
+        }
+        return Ok(());
+    }
 
-        let instructions: Vec<Instruction> = parse_instructions(&input);
-        sub.interpret_pt2(&instructions);
-        assert_eq!(sub.x * sub.y, 900);
+    {
+        let mut sub = Submarine::default();
+        sub.run(&instructions, &Direct)
+            .expect("Could not follow instructions.");
+        println!("part1: {:?}", sub.product().expect("Product overflows."));
     }
+    {
+        let mut sub = Submarine::default();
+        let mut trajectory = Trajectory::default();
+        sub.record(&instructions, &Aim, &mut trajectory)
+            .expect("Could not follow instructions.");
+        println!("part2: {:?}", sub.product().expect("Product overflows."));
+        println!(
+            "max depth: {:?}",
+            trajectory.max_depth().expect("Empty trajectory.")
+        );
+        println!("distance: {:.1}", trajectory.distance());
+    }
+
+    Ok(())
 }
@@ -0,0 +1,435 @@
+use std::fmt;
+
+use crate::Instruction;
+
+// what a plan should be shortest in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Objective {
+    #[default]
+    Instructions,
+    // the sum of the units of all instructions
+    Units,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    pub max_aim: Option<u32>,
+    pub max_depth: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanError {
+    // a depth below the surface needs at least one step forward
+    NoForward,
+    // even diving at the maximum aim all the way does not reach the depth
+    AimLimit { max_aim: u32 },
+    DepthLimit { max_depth: u64 },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::NoForward => {
+                write!(f, "a depth below 0 needs a horizontal position above 0")
+            }
+            PlanError::AimLimit { max_aim } => {
+                write!(f, "the depth is out of reach with aim at most {}", max_aim)
+            }
+            PlanError::DepthLimit { max_depth } => {
+                write!(f, "the depth is below the maximum depth {}", max_depth)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+// instructions that take a sub from the surface to `(x, depth)` under the
+// aim model.
+//
+// plans only use forward, down and up and never let the aim go below 0, so
+// the depth never decreases and the target is the deepest point of the
+// path. the forward units always add up to `x`, so the fewest units come
+// from the lowest final aim, `ceil(depth / x)`. with `depth = a * x + s` a
+// plan with that final aim is
+//
+//     down a, forward x - s, down 1, forward s
+//
+// which makes four instructions the most any target needs. the shorter
+// `down, forward` and `forward, down, forward` are used when they fit, for
+// fewest units only if they do not cost more units.
+pub fn plan(
+    x: u32,
+    depth: u64,
+    objective: Objective,
+    limits: Limits,
+) -> Result<Vec<Instruction>, PlanError> {
+    let max_aim = limits.max_aim.unwrap_or(u32::MAX);
+    if let Some(max_depth) = limits.max_depth {
+        if depth > max_depth {
+            return Err(PlanError::DepthLimit { max_depth });
+        }
+    }
+    if depth == 0 {
+        return Ok(forward(x, &[]));
+    }
+    if x == 0 {
+        return Err(PlanError::NoForward);
+    }
+    if depth > u64::from(max_aim) * u64::from(x) {
+        return Err(PlanError::AimLimit { max_aim });
+    }
+
+    let x = u64::from(x);
+    let lowest_aim = depth.div_ceil(x);
+    let max_aim = match objective {
+        Objective::Instructions => max_aim.into(),
+        Objective::Units => lowest_aim,
+    };
+    // down, forward
+    if depth.is_multiple_of(x) {
+        return Ok(forward(0, &[(depth / x, x)]));
+    }
+    // forward, down, forward, taking the most forward at the lowest aim
+    if let Some(f) = largest_divisor(depth, x, max_aim) {
+        return Ok(forward((x - f) as u32, &[(depth / f, f)]));
+    }
+    // down, forward, down, forward
+    let (a, s) = (depth / x, depth % x);
+    Ok(forward(0, &[(a, x - s), (a + 1, s)]))
+}
+
+// `forward first`, then stretches of forward units at increasing aims,
+// leaving out the empty instructions
+fn forward(first: u32, stretches: &[(u64, u64)]) -> Vec<Instruction> {
+    let mut plan = Vec::new();
+    if first > 0 {
+        plan.push(Instruction::Forward(first));
+    }
+    let mut aim = 0;
+    for &(next, units) in stretches {
+        plan.push(Instruction::Down((next - aim) as u32));
+        plan.push(Instruction::Forward(units as u32));
+        aim = next;
+    }
+    plan
+}
+
+// the largest divisor `f` of `n` with `f < x` and `n / f <= max_aim`.
+// trial division would take up to √n steps, some 4 billion for the deepest
+// targets, so `n` is factored and only its divisors are tried.
+fn largest_divisor(n: u64, x: u64, max_aim: u64) -> Option<u64> {
+    divisors(n)
+        .into_iter()
+        .filter(|&f| f < x && n / f <= max_aim)
+        .max()
+}
+
+// every divisor of `n`, in no particular order
+fn divisors(n: u64) -> Vec<u64> {
+    let mut primes = Vec::new();
+    prime_factors(n, &mut primes);
+    primes.sort_unstable();
+    let mut divisors = vec![1];
+    for group in primes.chunk_by(|a, b| a == b) {
+        let (prime, count) = (group[0], group.len());
+        let mut more = Vec::with_capacity(divisors.len() * count);
+        for &d in &divisors {
+            let mut power = d;
+            for _ in 0..count {
+                power *= prime;
+                more.push(power);
+            }
+        }
+        divisors.extend(more);
+    }
+    divisors
+}
+
+// the prime factors of `n`, with repeats
+fn prime_factors(n: u64, out: &mut Vec<u64>) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        out.push(n);
+        return;
+    }
+    let d = rho(n);
+    prime_factors(d, out);
+    prime_factors(n / d, out);
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(m)) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// Miller-Rabin, these bases are enough for every u64
+fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(&p) = BASES.iter().find(|&&p| n.is_multiple_of(p)) {
+        return n == p;
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'bases: for a in BASES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// a factor of a composite `n` other than 1 and `n`, Pollard's rho
+fn rho(n: u64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+    for c in 1.. {
+        let next = |x: u64| ((u128::from(x) * u128::from(x) + c) % u128::from(n)) as u64;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = next(x);
+            y = next(next(y));
+            d = gcd(x.abs_diff(y), n);
+        }
+        // the cycle closed without a factor, try another polynomial
+        if d != n {
+            return d;
+        }
+    }
+    unreachable!()
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Aim, Submarine};
+
+    fn reach(plan: &[Instruction]) -> (i64, i64, i64) {
+        let mut sub = Submarine::default();
+        sub.run(plan, &Aim).unwrap();
+        (sub.x, sub.y, sub.aim)
+    }
+
+    fn units(plan: &[Instruction]) -> u64 {
+        plan.iter()
+            .map(|&i| match i {
+                Instruction::Forward(u)
+                | Instruction::Down(u)
+                | Instruction::Up(u)
+                | Instruction::Back(u)
+                | Instruction::Left(u)
+                | Instruction::Right(u) => u64::from(u),
+            })
+            .sum()
+    }
+
+    #[test]
+    fn example_target() {
+        let steps = plan(15, 60, Objective::Instructions, Limits::default()).unwrap();
+        assert_eq!(steps, vec![Instruction::Down(4), Instruction::Forward(15)]);
+
+        let limits = Limits {
+            max_aim: Some(3),
+            max_depth: None,
+        };
+        let steps = plan(15, 60, Objective::Instructions, limits);
+        assert_eq!(steps, Err(PlanError::AimLimit { max_aim: 3 }));
+    }
+
+    #[test]
+    fn shapes() {
+        // 48 = 4 * 12, after 3 at aim 0
+        for objective in [Objective::Instructions, Objective::Units] {
+            let steps = plan(15, 48, objective, Limits::default()).unwrap();
+            assert_eq!(
+                steps,
+                vec![
+                    Instruction::Forward(3),
+                    Instruction::Down(4),
+                    Instruction::Forward(12)
+                ]
+            );
+        }
+
+        // 7 = 7 * 1 is the shortest, but aim 7 costs more than 7 = 1 * 3 + 2 * 2
+        let steps = plan(5, 7, Objective::Instructions, Limits::default()).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Instruction::Forward(4),
+                Instruction::Down(7),
+                Instruction::Forward(1)
+            ]
+        );
+        assert_eq!(units(&steps), 12);
+        let steps = plan(5, 7, Objective::Units, Limits::default()).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Instruction::Down(1),
+                Instruction::Forward(3),
+                Instruction::Down(1),
+                Instruction::Forward(2)
+            ]
+        );
+        assert_eq!(units(&steps), 7);
+
+        // the same when the aim may not go above 3
+        let limits = Limits {
+            max_aim: Some(3),
+            max_depth: None,
+        };
+        assert_eq!(plan(5, 7, Objective::Instructions, limits), Ok(steps));
+    }
+
+    #[test]
+    fn reaches_every_target() {
+        for max_aim in 1..5 {
+            let limits = Limits {
+                max_aim: Some(max_aim),
+                max_depth: None,
+            };
+            for x in 0..10 {
+                for depth in 0..40 {
+                    let reachable = depth == 0 || depth <= u64::from(max_aim * x);
+                    for objective in [Objective::Instructions, Objective::Units] {
+                        let steps = plan(x, depth, objective, limits);
+                        assert_eq!(steps.is_ok(), reachable, "{} {} {}", x, depth, max_aim);
+                        if let Ok(steps) = steps {
+                            assert_eq!(reach(&steps).0, i64::from(x));
+                            assert_eq!(reach(&steps).1, depth as i64);
+                            assert!(steps.len() <= 4);
+                            if objective == Objective::Units {
+                                let lowest_aim = depth.div_ceil(u64::from(x).max(1));
+                                assert_eq!(units(&steps), u64::from(x) + lowest_aim);
+                            }
+                            let mut aim = 0;
+                            for instruction in &steps {
+                                match instruction {
+                                    Instruction::Down(u) => aim += u,
+                                    Instruction::Up(u) => aim -= u,
+                                    _ => {}
+                                }
+                                assert!(aim <= max_aim);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn largest_divisor_in_range() {
+        for n in 1..200 {
+            for x in 1..30 {
+                for max_aim in 1..30 {
+                    let expected = (1..x).rev().find(|&f| n % f == 0 && n / f <= max_aim);
+                    assert_eq!(largest_divisor(n, x, max_aim), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn factoring() {
+        let mut found = divisors(360);
+        found.sort_unstable();
+        assert_eq!(
+            found,
+            (1..=360).filter(|d| 360 % d == 0).collect::<Vec<_>>()
+        );
+        for n in [
+            1,
+            2,
+            u64::MAX,
+            u64::from(u32::MAX) * u64::from(u32::MAX),
+            // two primes just below 2^32
+            4_294_967_291 * 4_294_967_279,
+            18_446_744_073_709_551_557,
+        ] {
+            let mut primes = Vec::new();
+            prime_factors(n, &mut primes);
+            assert!(primes.iter().all(|&p| is_prime(p)), "{}", n);
+            assert_eq!(primes.iter().product::<u64>(), n);
+        }
+    }
+
+    #[test]
+    fn large_targets() {
+        // too deep for the i64 sub, so follow the plan in u128
+        let reach = |steps: &[Instruction]| {
+            let (mut x, mut depth, mut aim) = (0u128, 0u128, 0u128);
+            for &instruction in steps {
+                match instruction {
+                    Instruction::Down(u) => aim += u128::from(u),
+                    Instruction::Forward(u) => {
+                        x += u128::from(u);
+                        depth += aim * u128::from(u);
+                    }
+                    other => panic!("unexpected {}", other),
+                }
+            }
+            (x, depth)
+        };
+        let targets = [
+            (4_000_000_000, 15_999_999_999_000_000_001),
+            (u32::MAX, u64::from(u32::MAX) * u64::from(u32::MAX)),
+            (u32::MAX, u64::from(u32::MAX) * u64::from(u32::MAX) - 1),
+        ];
+        for (x, depth) in targets {
+            for objective in [Objective::Instructions, Objective::Units] {
+                let steps = plan(x, depth, objective, Limits::default()).unwrap();
+                assert_eq!(reach(&steps), (x.into(), depth.into()));
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let limits = Limits::default();
+        assert_eq!(plan(0, 0, Objective::Instructions, limits), Ok(vec![]));
+        assert_eq!(
+            plan(0, 5, Objective::Instructions, limits),
+            Err(PlanError::NoForward)
+        );
+        let limits = Limits {
+            max_aim: None,
+            max_depth: Some(10),
+        };
+        let err = plan(5, 11, Objective::Units, limits).unwrap_err();
+        assert_eq!(err.to_string(), "the depth is below the maximum depth 10");
+    }
+}
@@ -0,0 +1,236 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use crate::script::parse_script;
+use crate::{parse_instruction, Aim, Direct, Instruction, MoveError, MovementModel, Submarine};
+
+// the sub under both models, driven one command at a time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Session {
+    direct: Submarine,
+    aim: Submarine,
+    history: Vec<Instruction>,
+    // history length and subs before every command, for `undo`
+    checkpoints: Vec<(usize, Submarine, Submarine)>,
+}
+
+impl Session {
+    pub fn direct(&self) -> &Submarine {
+        &self.direct
+    }
+
+    pub fn aim(&self) -> &Submarine {
+        &self.aim
+    }
+
+    pub fn history(&self) -> &[Instruction] {
+        &self.history
+    }
+
+    // follow the instructions under both models as a single command. if any
+    // of them fails under either model nothing changes. steps in errors count
+    // from the start of the history, as they would in batch mode.
+    pub fn apply(&mut self, instructions: &[Instruction]) -> Result<(), MoveError> {
+        let mut direct = self.direct;
+        let mut aim = self.aim;
+        for (idx, instruction) in instructions.iter().enumerate() {
+            let step = self.history.len() + idx;
+            direct = Direct.step(&direct, *instruction, step)?;
+            aim = Aim.step(&aim, *instruction, step)?;
+        }
+        self.checkpoints
+            .push((self.history.len(), self.direct, self.aim));
+        self.direct = direct;
+        self.aim = aim;
+        self.history.extend_from_slice(instructions);
+        Ok(())
+    }
+
+    // revert the last command, `false` if there is none
+    pub fn undo(&mut self) -> bool {
+        match self.checkpoints.pop() {
+            Some((len, direct, aim)) => {
+                self.history.truncate(len);
+                self.direct = direct;
+                self.aim = aim;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Session::default();
+    }
+
+    pub fn status(&self) -> String {
+        let line = |name: &str, sub: &Submarine| {
+            let product = sub
+                .product()
+                .map_or("overflow".to_string(), |p| p.to_string());
+            format!(
+                "{:<7} x {}, depth {}, aim {}, product {}",
+                name, sub.x, sub.y, sub.aim, product
+            )
+        };
+        format!(
+            "{}\n{}",
+            line("direct:", &self.direct),
+            line("aim:", &self.aim)
+        )
+    }
+
+    // run a single line typed at the prompt, returning what to print
+    pub fn command(&mut self, line: &str) -> String {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match (name, argument.trim()) {
+            ("", _) => String::new(),
+            ("undo", "") if self.undo() => self.status(),
+            ("undo", "") => "nothing to undo".to_string(),
+            ("reset", "") => {
+                self.reset();
+                self.status()
+            }
+            ("history", "") => self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(step, instruction)| format!("{:>4}  {}", step, instruction))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ("load", "") => "load needs a file".to_string(),
+            ("load", path) => {
+                let loaded = fs::read_to_string(path)
+                    .map_err(|err| format!("{}: {}", path, err))
+                    .and_then(|script| {
+                        parse_script(&script).map_err(|err| format!("{}: {}", path, err))
+                    });
+                match loaded {
+                    Ok(instructions) => self.run(&instructions),
+                    Err(err) => err,
+                }
+            }
+            _ => match parse_instruction(line) {
+                Ok(instruction) => self.run(&[instruction]),
+                Err(err) => err,
+            },
+        }
+    }
+
+    fn run(&mut self, instructions: &[Instruction]) -> String {
+        match self.apply(instructions) {
+            Ok(()) => self.status(),
+            Err(err) => format!("{}, ignored", err),
+        }
+    }
+}
+
+// read commands until `quit` or the end of the input
+pub fn repl<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut session = Session::default();
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        let response = session.command(&line);
+        if !response.is_empty() {
+            writeln!(output, "{}", response)?;
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_instructions, EXAMPLE};
+
+    #[test]
+    fn matches_batch_mode() {
+        let mut session = Session::default();
+        for line in EXAMPLE.lines() {
+            session.command(line);
+        }
+        assert_eq!(session.history(), &parse_instructions(EXAMPLE)[..]);
+        assert_eq!(session.direct().product(), Some(150));
+        assert_eq!(session.aim().product(), Some(900));
+        assert_eq!(
+            session.status(),
+            "direct: x 15, depth 10, aim 0, product 150\n\
+             aim:    x 15, depth 60, aim 10, product 900"
+        );
+    }
+
+    #[test]
+    fn undo_and_reset() {
+        let mut session = Session::default();
+        session.command("forward 5");
+        session.command("down 5");
+        let before = session.clone();
+        session.command("forward 8");
+        assert!(session.undo());
+        assert_eq!(session, before);
+
+        // a failed command is not recorded
+        let err = session.command("up 6");
+        assert_eq!(
+            err,
+            "instruction 2 would take the sub above the surface to depth -1, ignored"
+        );
+        assert_eq!(session, before);
+
+        assert_eq!(session.command("history"), "   0  forward 5\n   1  down 5");
+        session.command("reset");
+        assert_eq!(session, Session::default());
+        assert_eq!(session.command("undo"), "nothing to undo");
+    }
+
+    #[test]
+    fn load_is_one_command() {
+        let mut session = Session::default();
+        session.command("forward 5");
+        // `repeat 2 { forward 1 down 1 }`
+        session.command("load stairs.sub");
+        assert_eq!(session.history().len(), 5);
+        assert_eq!((session.aim().x, session.aim().y), (7, 1));
+        session.undo();
+        assert_eq!(session.history().len(), 1);
+
+        assert!(session
+            .command("load /nonexistent/day02.sub")
+            .starts_with("/nonexistent/day02.sub: "));
+    }
+
+    #[test]
+    fn bad_input() {
+        let mut session = Session::default();
+        assert_eq!(
+            session.command("sideways 5"),
+            "Unknown direction: \"sideways\""
+        );
+        assert_eq!(
+            session.command("forward five"),
+            "Could not parse units: \"five\""
+        );
+        assert_eq!(session.command("   "), "");
+        assert!(session.history().is_empty());
+    }
+
+    #[test]
+    fn prompt() {
+        let mut output = Vec::new();
+        repl("forward 2\n\nquit\nforward 1\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "> direct: x 2, depth 0, aim 0, product 0\n\
+             aim:    x 2, depth 0, aim 0, product 0\n\
+             > > "
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Instruction;
+
+// scripts that expand to more instructions than this are rejected
+pub const MAX_INSTRUCTIONS: usize = 10_000_000;
+
+const KEYWORDS: [&str; 8] = [
+    "forward", "down", "up", "back", "left", "right", "repeat", "macro",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptError {
+    // 1-based line of the script
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+// expand a navigation script into instructions. on top of the puzzle input
+// format, scripts can contain
+//
+//     # comments and blank lines
+//     back 3
+//     repeat 4 { forward 2 down 1 }
+//     macro zigzag {
+//         down 2
+//         forward 1
+//         up 2
+//     }
+//     zigzag
+//
+// macros have to be defined at the top level before they are used.
+pub fn parse_script(input: &str) -> Result<Vec<Instruction>, ScriptError> {
+    let mut parser = Parser {
+        tokens: tokenize(input),
+        pos: 0,
+        macros: HashMap::new(),
+    };
+    let mut instructions = Vec::new();
+    parser.block(None, &mut instructions)?;
+    Ok(instructions)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+// words, with braces as tokens of their own
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        for word in line.split_whitespace() {
+            let mut rest = word;
+            while !rest.is_empty() {
+                let end = match rest.find(['{', '}']) {
+                    Some(0) => 1,
+                    Some(brace) => brace,
+                    None => rest.len(),
+                };
+                tokens.push(Token {
+                    text: &rest[..end],
+                    line: idx + 1,
+                });
+                rest = &rest[end..];
+            }
+        }
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    macros: HashMap<&'a str, Vec<Instruction>>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn error<T>(line: usize, message: String) -> Result<T, ScriptError> {
+        Err(ScriptError { line, message })
+    }
+
+    // the line of the last token, for errors at the end of the script
+    fn last_line(&self) -> usize {
+        self.tokens.last().map_or(1, |token| token.line)
+    }
+
+    fn number(&mut self, after: Token) -> Result<u32, ScriptError> {
+        match self.next() {
+            Some(token) => token.text.parse().or_else(|_| {
+                Self::error(
+                    token.line,
+                    format!(
+                        "Expected a number after {:?}, found {:?}",
+                        after.text, token.text
+                    ),
+                )
+            }),
+            None => Self::error(
+                self.last_line(),
+                format!("Expected a number after {:?}", after.text),
+            ),
+        }
+    }
+
+    fn open_brace(&mut self, after: Token) -> Result<(), ScriptError> {
+        match self.next() {
+            Some(token) if token.text == "{" => Ok(()),
+            Some(token) => Self::error(
+                token.line,
+                format!("Expected {{ after {:?}, found {:?}", after.text, token.text),
+            ),
+            None => Self::error(
+                self.last_line(),
+                format!("Expected {{ after {:?}", after.text),
+            ),
+        }
+    }
+
+    fn too_long<T>(line: usize) -> Result<T, ScriptError> {
+        Self::error(
+            line,
+            format!(
+                "Script expands to more than {} instructions",
+                MAX_INSTRUCTIONS
+            ),
+        )
+    }
+
+    fn push(
+        out: &mut Vec<Instruction>,
+        more: &[Instruction],
+        line: usize,
+    ) -> Result<(), ScriptError> {
+        if out.len() + more.len() > MAX_INSTRUCTIONS {
+            return Self::too_long(line);
+        }
+        out.extend_from_slice(more);
+        Ok(())
+    }
+
+    // statements up to the `}` closing the block opened by `opener`, or up
+    // to the end of the script at the top level
+    fn block(
+        &mut self,
+        opener: Option<Token>,
+        out: &mut Vec<Instruction>,
+    ) -> Result<(), ScriptError> {
+        loop {
+            let token = match (self.next(), opener) {
+                (Some(token), _) => token,
+                (None, None) => return Ok(()),
+                (None, Some(opener)) => {
+                    return Self::error(opener.line, format!("Missing }} for {:?}", opener.text))
+                }
+            };
+            match token.text {
+                "}" if opener.is_some() => return Ok(()),
+                "}" => return Self::error(token.line, "Unexpected }".to_string()),
+                "repeat" => {
+                    let times = self.number(token)?;
+                    self.open_brace(token)?;
+                    let mut body = Vec::new();
+                    self.block(Some(token), &mut body)?;
+                    // a single copy of the whole expansion, so an empty body
+                    // costs nothing however often it is repeated
+                    let len = body.len().saturating_mul(times as usize);
+                    if out.len().saturating_add(len) > MAX_INSTRUCTIONS {
+                        return Self::too_long(token.line);
+                    }
+                    out.extend(body.iter().cycle().take(len));
+                }
+                "macro" => {
+                    if opener.is_some() {
+                        return Self::error(
+                            token.line,
+                            "Macros have to be defined at the top level".to_string(),
+                        );
+                    }
+                    let name = match self.next() {
+                        Some(name) if is_name(name.text) => name,
+                        Some(name) => {
+                            return Self::error(
+                                name.line,
+                                format!("Invalid macro name: {:?}", name.text),
+                            )
+                        }
+                        None => return Self::error(token.line, "Missing macro name".to_string()),
+                    };
+                    if self.macros.contains_key(name.text) {
+                        return Self::error(
+                            name.line,
+                            format!("Macro {:?} is already defined", name.text),
+                        );
+                    }
+                    self.open_brace(name)?;
+                    let mut body = Vec::new();
+                    self.block(Some(token), &mut body)?;
+                    self.macros.insert(name.text, body);
+                }
+                text => {
+                    if let Some(body) = self.macros.get(text) {
+                        let body = body.clone();
+                        Self::push(out, &body, token.line)?;
+                    } else if KEYWORDS.contains(&text) {
+                        let units = self.number(token)?;
+                        let instruction = Instruction::new(text, units).unwrap();
+                        Self::push(out, &[instruction], token.line)?;
+                    } else {
+                        return Self::error(token.line, format!("Unknown command: {:?}", text));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_name(text: &str) -> bool {
+    !KEYWORDS.contains(&text)
+        && text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_instructions, Aim, Submarine, EXAMPLE};
+
+    #[test]
+    fn puzzle_format() {
+        assert_eq!(parse_script(EXAMPLE), Ok(parse_instructions(EXAMPLE)));
+    }
+
+    #[test]
+    fn blocks_and_macros() {
+        let script = "# dive in steps\n\
+                      \n\
+                      macro step {\n\
+                      \x20   down 1\n\
+                      \x20   forward 2 # two forward\n\
+                      }\n\
+                      macro stairs { repeat 2 { step } }\n\
+                      stairs\n\
+                      repeat 2 {up 1}back 3\n";
+        let instructions = parse_script(script).unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::Down(1),
+                Instruction::Forward(2),
+                Instruction::Down(1),
+                Instruction::Forward(2),
+                Instruction::Up(1),
+                Instruction::Up(1),
+                Instruction::Back(3),
+            ]
+        );
+
+        let mut sub = Submarine::default();
+        sub.run(&instructions, &Aim).unwrap();
+        assert_eq!((sub.x, sub.y, sub.aim), (1, 6, 0));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |script: &str| parse_script(script).unwrap_err();
+        assert_eq!(
+            err("forward 1\nsideways 2"),
+            ScriptError {
+                line: 2,
+                message: "Unknown command: \"sideways\"".to_string()
+            }
+        );
+        assert_eq!(
+            err("forward x").to_string(),
+            "line 1: Expected a number after \"forward\", found \"x\""
+        );
+        assert_eq!(err("down").message, "Expected a number after \"down\"");
+        assert_eq!(
+            err("repeat 2\nforward 1").message,
+            "Expected { after \"repeat\", found \"forward\""
+        );
+        assert_eq!(err("\nrepeat 2 {\nforward 1\n").line, 2);
+        assert_eq!(err("forward 1 }").message, "Unexpected }");
+        assert_eq!(err("zigzag\nmacro zigzag { up 1 }").line, 1);
+        assert_eq!(err("macro a { a }").message, "Unknown command: \"a\"");
+        assert_eq!(err("macro a { }\nmacro a { }").line, 2);
+        assert_eq!(err("macro up { }").message, "Invalid macro name: \"up\"");
+        assert_eq!(
+            err("repeat 2 { macro a { } }").message,
+            "Macros have to be defined at the top level"
+        );
+        assert_eq!(
+            err("repeat 100000 { repeat 100000 { forward 1 } }").message,
+            "Script expands to more than 10000000 instructions"
+        );
+    }
+
+    #[test]
+    fn empty_repeat() {
+        // nothing to expand, however often
+        assert_eq!(parse_script("repeat 4294967295 { }"), Ok(vec![]));
+        assert_eq!(
+            parse_script("repeat 4294967295 { repeat 4294967295 { } }\nup 1"),
+            Ok(vec![Instruction::Up(1)])
+        );
+    }
+}
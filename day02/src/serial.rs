@@ -0,0 +1,407 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::{run, Instruction, MoveError, MovementModel, Submarine};
+
+// JSON uses serde's default layout with lowercase names, so an instruction
+// is `{"forward":5}`. the binary format is bincode with variable length
+// integers, which takes two bytes for most instructions; instruction files
+// are just one instruction after another.
+fn binary() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    // a checkpoint taken from another instruction file
+    WrongInput,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Io(err) => write!(f, "could not read or write: {}", err),
+            FormatError::Json(err) => write!(f, "invalid JSON: {}", err),
+            FormatError::Binary(err) => write!(f, "invalid binary data: {}", err),
+            FormatError::WrongInput => {
+                write!(f, "the checkpoint was taken from a different input")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<io::Error> for FormatError {
+    fn from(err: io::Error) -> Self {
+        FormatError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(err: serde_json::Error) -> Self {
+        FormatError::Json(err)
+    }
+}
+
+impl From<bincode::Error> for FormatError {
+    fn from(err: bincode::Error) -> Self {
+        FormatError::Binary(err)
+    }
+}
+
+pub fn instructions_to_json(instructions: &[Instruction]) -> String {
+    serde_json::to_string(instructions).unwrap()
+}
+
+pub fn instructions_from_json(json: &str) -> Result<Vec<Instruction>, FormatError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn write_instructions<W: Write>(
+    mut writer: W,
+    instructions: &[Instruction],
+) -> Result<(), FormatError> {
+    for instruction in instructions {
+        binary().serialize_into(&mut writer, instruction)?;
+    }
+    Ok(())
+}
+
+// reads a binary instruction file one instruction at a time, so huge files
+// never have to be in memory at once
+pub struct InstructionReader<R> {
+    reader: R,
+}
+
+impl<R: BufRead> InstructionReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: BufRead> Iterator for InstructionReader<R> {
+    type Item = Result<Instruction, FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the end of the file between two instructions is the end of the
+        // stream, anywhere else it is an error
+        match self.reader.fill_buf() {
+            Ok([]) => None,
+            Ok(_) => Some(
+                binary()
+                    .deserialize_from(&mut self.reader)
+                    .map_err(FormatError::from),
+            ),
+            Err(err) => Some(Err(err.into())),
+        }
+    }
+}
+
+// identifies the instruction file a checkpoint belongs to, so it is not
+// resumed against another one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub len: u64,
+    // FNV-1a, which unlike `DefaultHasher` is the same on every build
+    pub hash: u64,
+}
+
+impl Fingerprint {
+    pub fn of<R: Read>(reader: R) -> io::Result<Self> {
+        let mut fingerprint = Fingerprint {
+            len: 0,
+            hash: 0xcbf2_9ce4_8422_2325,
+        };
+        let mut reader = BufReader::new(reader);
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(fingerprint);
+            }
+            for &byte in buf {
+                fingerprint.hash =
+                    (fingerprint.hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+            }
+            let len = buf.len();
+            fingerprint.len += len as u64;
+            reader.consume(len);
+        }
+    }
+}
+
+// why a resumed run stopped early
+#[derive(Debug)]
+pub enum ResumeError {
+    Read(FormatError),
+    Move(MoveError),
+}
+
+impl fmt::Display for ResumeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResumeError::Read(err) => write!(f, "{}", err),
+            ResumeError::Move(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ResumeError {}
+
+impl From<FormatError> for ResumeError {
+    fn from(err: FormatError) -> Self {
+        ResumeError::Read(err)
+    }
+}
+
+impl From<MoveError> for ResumeError {
+    fn from(err: MoveError) -> Self {
+        ResumeError::Move(err)
+    }
+}
+
+// a run of the instructions in `input` stopped before instruction `step`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub step: usize,
+    pub sub: Submarine,
+    pub input: Fingerprint,
+}
+
+impl Checkpoint {
+    // the start of a run
+    pub fn new(input: Fingerprint) -> Self {
+        Self {
+            input,
+            ..Default::default()
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, FormatError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary().serialize(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        Ok(binary().deserialize(bytes)?)
+    }
+
+    pub fn check_input(&self, input: Fingerprint) -> Result<(), FormatError> {
+        if self.input == input {
+            Ok(())
+        } else {
+            Err(FormatError::WrongInput)
+        }
+    }
+
+    // carry on with the instructions from `step` on, which count from the
+    // start of the whole run, reading them as it goes so an
+    // `InstructionReader` never holds more than one. `save` gets a
+    // checkpoint every `every` instructions; on failure the checkpoint is
+    // left before the instruction that failed.
+    pub fn resume<I, M, F>(
+        &mut self,
+        instructions: I,
+        model: &M,
+        every: usize,
+        mut save: F,
+    ) -> Result<(), ResumeError>
+    where
+        I: IntoIterator<Item = Result<Instruction, FormatError>>,
+        M: MovementModel + ?Sized,
+        F: FnMut(&Checkpoint),
+    {
+        let mut instructions = instructions.into_iter();
+        // the instructions before the checkpoint still have to be read past
+        for skipped in instructions.by_ref().take(self.step) {
+            skipped?;
+        }
+        let mut failure = None;
+        let rest = instructions
+            .map_while(|instruction| instruction.map_err(|err| failure = Some(err)).ok());
+        let input = self.input;
+        let mut done = self.step;
+        let result = run(&mut self.sub, model, rest, self.step, |step, _, sub| {
+            done = step + 1;
+            if every > 0 && done.is_multiple_of(every) {
+                save(&Checkpoint {
+                    step: done,
+                    sub: *sub,
+                    input,
+                });
+            }
+        });
+        self.step = done;
+        result?;
+        match failure {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_instructions, Aim, Surface, EXAMPLE};
+
+    fn example() -> Vec<Instruction> {
+        parse_instructions(EXAMPLE)
+    }
+
+    fn stream(
+        instructions: Vec<Instruction>,
+    ) -> impl Iterator<Item = Result<Instruction, FormatError>> {
+        instructions.into_iter().map(Ok)
+    }
+
+    #[test]
+    fn json() {
+        let json = instructions_to_json(&example()[..3]);
+        assert_eq!(json, r#"[{"forward":5},{"down":5},{"forward":8}]"#);
+        assert_eq!(instructions_from_json(&json).unwrap(), &example()[..3]);
+        assert!(matches!(
+            instructions_from_json(r#"[{"sideways":5}]"#),
+            Err(FormatError::Json(_))
+        ));
+
+        let checkpoint = Checkpoint {
+            step: 3,
+            sub: Submarine {
+                x: 13,
+                y: 40,
+                aim: 5,
+                surface: Surface::Clamp,
+            },
+            input: Fingerprint { len: 54, hash: 7 },
+        };
+        let json = checkpoint.to_json();
+        assert_eq!(
+            json,
+            r#"{"step":3,"sub":{"x":13,"y":40,"aim":5,"surface":"clamp"},"input":{"len":54,"hash":7}}"#
+        );
+        assert_eq!(Checkpoint::from_json(&json).unwrap(), checkpoint);
+    }
+
+    #[test]
+    fn binary_instructions() {
+        let instructions = vec![
+            Instruction::Forward(5),
+            Instruction::Right(90),
+            Instruction::Down(u32::MAX),
+        ];
+        let mut bytes = Vec::new();
+        write_instructions(&mut bytes, &instructions).unwrap();
+        // a byte for the variant, then the units in as few bytes as fit
+        assert_eq!(bytes, vec![2, 5, 5, 90, 0, 252, 255, 255, 255, 255]);
+
+        let read: Vec<Instruction> = InstructionReader::new(&bytes[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, instructions);
+
+        // a truncated instruction is an error, not the end
+        let mut read = InstructionReader::new(&bytes[..bytes.len() - 1]);
+        assert!(read.next().unwrap().is_ok());
+        assert!(read.next().unwrap().is_ok());
+        assert!(matches!(read.next(), Some(Err(FormatError::Binary(_)))));
+    }
+
+    #[test]
+    fn checkpoint_and_resume() {
+        let mut saved = Vec::new();
+        let mut checkpoint = Checkpoint::default();
+        checkpoint
+            .resume(stream(example()).take(4), &Aim, 2, |c| {
+                saved.push(c.to_bytes())
+            })
+            .unwrap();
+        assert_eq!(saved.len(), 2);
+
+        // pick up from the first checkpoint as if after a restart
+        let mut resumed = Checkpoint::from_bytes(&saved[0]).unwrap();
+        assert_eq!(resumed.step, 2);
+        resumed.resume(stream(example()), &Aim, 0, |_| {}).unwrap();
+        assert_eq!(resumed.step, 6);
+        assert_eq!(resumed.sub.product(), Some(900));
+
+        assert!(matches!(
+            Checkpoint::from_bytes(&saved[0][..2]),
+            Err(FormatError::Binary(_))
+        ));
+    }
+
+    #[test]
+    fn resume_failure() {
+        let instructions = parse_instructions("down 2\nforward 1\nup 5\nforward 3");
+        let mut checkpoint = Checkpoint::default();
+        let err = checkpoint
+            .resume(stream(instructions), &crate::Direct, 1, |_| {})
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ResumeError::Move(MoveError::AboveSurface { step: 2, depth: -3 })
+        ));
+        assert_eq!(checkpoint.step, 2);
+        assert_eq!((checkpoint.sub.x, checkpoint.sub.y), (1, 2));
+    }
+
+    #[test]
+    fn resume_streamed() {
+        let mut bytes = Vec::new();
+        write_instructions(&mut bytes, &example()).unwrap();
+        let input = Fingerprint::of(&bytes[..]).unwrap();
+        assert_eq!(input.len, bytes.len() as u64);
+
+        let mut saved = Vec::new();
+        let mut checkpoint = Checkpoint::new(input);
+        // the last instruction is cut short
+        let reader = InstructionReader::new(&bytes[..bytes.len() - 1]);
+        let err = checkpoint
+            .resume(reader, &Aim, 2, |c| saved.push(*c))
+            .unwrap_err();
+        assert!(matches!(err, ResumeError::Read(FormatError::Binary(_))));
+        assert_eq!(checkpoint.step, 5);
+        assert_eq!(saved.len(), 2);
+
+        // pick up from a saved checkpoint against the whole file
+        let mut resumed = saved[1];
+        assert_eq!(
+            resumed.check_input(input).map_err(|e| e.to_string()),
+            Ok(())
+        );
+        resumed
+            .resume(InstructionReader::new(&bytes[..]), &Aim, 0, |_| {})
+            .unwrap();
+        assert_eq!((resumed.step, resumed.sub.product()), (6, Some(900)));
+    }
+
+    #[test]
+    fn wrong_input() {
+        let input = Fingerprint::of(EXAMPLE.as_bytes()).unwrap();
+        let checkpoint = Checkpoint::new(input);
+        // same length, one instruction changed
+        let edited = EXAMPLE.replace("up 3", "up 4");
+        let other = Fingerprint::of(edited.as_bytes()).unwrap();
+        assert_eq!(other.len, input.len);
+        let err = checkpoint.check_input(other).unwrap_err();
+        assert!(matches!(err, FormatError::WrongInput));
+        assert_eq!(
+            err.to_string(),
+            "the checkpoint was taken from a different input"
+        );
+    }
+}
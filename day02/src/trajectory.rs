@@ -0,0 +1,211 @@
+use std::fmt::Write;
+
+use crate::{Instruction, Submarine};
+
+// the state of the sub after an instruction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    // `None` for the starting position
+    pub instruction: Option<Instruction>,
+    pub x: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+// every position of a run, starting with the one before the first
+// instruction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trajectory {
+    points: Vec<Point>,
+}
+
+impl Trajectory {
+    pub fn push(&mut self, instruction: Option<Instruction>, sub: &Submarine) {
+        self.points.push(Point {
+            instruction,
+            x: sub.x,
+            depth: sub.y,
+            aim: sub.aim,
+        });
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Point> {
+        self.points.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn max_depth(&self) -> Option<i64> {
+        self.points.iter().map(|p| p.depth).max()
+    }
+
+    // length of the path through the water
+    pub fn distance(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|pair| {
+                let dx = (pair[1].x - pair[0].x) as f64;
+                let dy = (pair[1].depth - pair[0].depth) as f64;
+                dx.hypot(dy)
+            })
+            .sum()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("step,instruction,x,depth,aim\n");
+        for (step, point) in self.points.iter().enumerate() {
+            let instruction = point.instruction.map(|i| i.to_string()).unwrap_or_default();
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                step, instruction, point.x, point.depth, point.aim
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    // the path with depth growing downwards and the deepest point marked
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let min_x = self.points.iter().map(|p| p.x).min().unwrap_or(0);
+        let max_x = self.points.iter().map(|p| p.x).max().unwrap_or(0);
+        let min_y = self.points.iter().map(|p| p.depth).min().unwrap_or(0);
+        let max_y = self.max_depth().unwrap_or(0);
+        let scale = |v: i64, min: i64, max: i64, size: u32| {
+            if max > min {
+                (v - min) as f64 / (max - min) as f64 * size as f64
+            } else {
+                0.0
+            }
+        };
+        let point = |p: &Point| {
+            (
+                scale(p.x, min_x, max_x, width),
+                scale(p.depth, min_y, max_y, height),
+            )
+        };
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"-5 -5 {} {}\">",
+            width + 10,
+            height + 10,
+            width + 10,
+            height + 10
+        )
+        .unwrap();
+        let path: Vec<String> = self
+            .points
+            .iter()
+            .map(|p| {
+                let (x, y) = point(p);
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        writeln!(
+            out,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"#1f77b4\" stroke-width=\"1\"/>",
+            path.join(" ")
+        )
+        .unwrap();
+        if let Some(deepest) = self.points.iter().find(|p| p.depth == max_y) {
+            let (x, y) = point(deepest);
+            writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"#d62728\">\
+                 <title>max depth {}</title></circle>",
+                x, y, deepest.depth
+            )
+            .unwrap();
+        }
+        writeln!(out, "</svg>").unwrap();
+        out
+    }
+}
+
+impl<'a> IntoIterator for &'a Trajectory {
+    type Item = &'a Point;
+    type IntoIter = std::slice::Iter<'a, Point>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_instructions, Aim, Direct, MoveError, EXAMPLE};
+
+    fn example() -> Vec<Instruction> {
+        parse_instructions(EXAMPLE)
+    }
+
+    #[test]
+    fn record_example() {
+        let mut sub = Submarine::default();
+        let mut trajectory = Trajectory::default();
+        sub.record(&example(), &Aim, &mut trajectory).unwrap();
+
+        assert_eq!(trajectory.len(), 7);
+        let path: Vec<(i64, i64, i64)> = trajectory.iter().map(|p| (p.x, p.depth, p.aim)).collect();
+        assert_eq!(
+            path,
+            vec![
+                (0, 0, 0),
+                (5, 0, 0),
+                (5, 0, 5),
+                (13, 40, 5),
+                (13, 40, 2),
+                (13, 40, 10),
+                (15, 60, 10)
+            ]
+        );
+        assert_eq!(trajectory.max_depth(), Some(60));
+        // 5 + sqrt(8² + 40²) + sqrt(2² + 20²)
+        assert!((trajectory.distance() - 65.892).abs() < 1e-3);
+    }
+
+    #[test]
+    fn record_until_failure() {
+        let instructions = parse_instructions("down 2\nforward 1\nup 5\nforward 3");
+        let mut sub = Submarine::default();
+        let mut trajectory = Trajectory::default();
+        let err = sub
+            .record(&instructions, &Direct, &mut trajectory)
+            .unwrap_err();
+        assert_eq!(err, MoveError::AboveSurface { step: 2, depth: -3 });
+        assert_eq!(trajectory.len(), 3);
+        assert_eq!(
+            trajectory.iter().last().unwrap().instruction,
+            Some(Instruction::Forward(1))
+        );
+    }
+
+    #[test]
+    fn export() {
+        let mut sub = Submarine::default();
+        let mut trajectory = Trajectory::default();
+        sub.record(&example()[..3], &Aim, &mut trajectory).unwrap();
+        assert_eq!(
+            trajectory.to_csv(),
+            "step,instruction,x,depth,aim\n\
+             0,,0,0,0\n\
+             1,forward 5,5,0,0\n\
+             2,down 5,5,0,5\n\
+             3,forward 8,13,40,5\n"
+        );
+
+        let svg = trajectory.to_svg(130, 40);
+        assert!(svg.contains("points=\"0.0,0.0 50.0,0.0 50.0,0.0 130.0,40.0\""));
+        assert!(svg.contains("<title>max depth 40</title>"));
+    }
+}
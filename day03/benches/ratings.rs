@@ -0,0 +1,72 @@
+// compares filtering the report per bit with walking a trie, run with
+// `cargo bench`. the trie is timed with and without building it.
+use day03::trie::Trie;
+use day03::{co2_scrubber_rating, oxygen_generator_rating, Report};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 10;
+
+// about 90% of the values that fit in `width` bits, in a shuffled order.
+// like puzzle inputs the rows are distinct and dense enough that the CO2
+// rating does not run out of rows.
+fn report(width: usize) -> Report {
+    let mut state = 0x9e3779b97f4a7c15u64;
+    let mut values: Vec<(u64, u64)> = (0..1u64 << width)
+        .map(|value| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state, value)
+        })
+        .filter(|(key, _)| key % 10 != 0)
+        .collect();
+    values.sort_unstable();
+    let input: String = values
+        .iter()
+        .map(|(_, value)| format!("{:0width$b}\n", value, width = width))
+        .collect();
+    Report::parse(&input).expect("Could not parse report.")
+}
+
+fn time<T, F: FnMut() -> T>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    println!(
+        "{:>9} {:>6} {:>12} {:>12} {:>12}",
+        "rows", "width", "filter", "trie+build", "trie"
+    );
+    for width in [12, 17, 20] {
+        let report = report(width);
+        let filter = time(|| {
+            (
+                oxygen_generator_rating(&report),
+                co2_scrubber_rating(&report),
+            )
+        });
+        let build = time(|| {
+            let trie = Trie::new(&report);
+            (trie.oxygen_generator_rating(), trie.co2_scrubber_rating())
+        });
+        let trie = Trie::new(&report);
+        let walk = time(|| (trie.oxygen_generator_rating(), trie.co2_scrubber_rating()));
+        assert_eq!(
+            trie.oxygen_generator_rating(),
+            oxygen_generator_rating(&report)
+        );
+        println!(
+            "{:>9} {:>6} {:>12?} {:>12?} {:>12?}",
+            report.len(),
+            width,
+            filter,
+            build,
+            walk
+        );
+    }
+}
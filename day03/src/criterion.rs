@@ -0,0 +1,191 @@
+use std::fmt;
+use std::str::FromStr;
+
+use num_bigint::BigUint;
+
+use crate::{filter_report, Bits, RatingError, Report};
+
+// what to do when a column has as many zeros as ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    Zero,
+    One,
+    // leave the rows as they are and go on with the next column
+    KeepBoth,
+}
+
+// which bit to keep in each round of a rating search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitCriterion {
+    MostCommon(TieBreak),
+    LeastCommon(TieBreak),
+}
+
+impl BitCriterion {
+    // the bit to keep given the zeros and ones among the rows left, `None`
+    // to keep both. a bit no row has is the least common one.
+    pub fn keep(self, num_zeros: usize, num_ones: usize) -> Option<bool> {
+        let (more_ones, tie) = match self {
+            BitCriterion::MostCommon(tie) => (true, tie),
+            BitCriterion::LeastCommon(tie) => (false, tie),
+        };
+        match num_ones.cmp(&num_zeros) {
+            std::cmp::Ordering::Greater => Some(more_ones),
+            std::cmp::Ordering::Less => Some(!more_ones),
+            std::cmp::Ordering::Equal => match tie {
+                TieBreak::Zero => Some(false),
+                TieBreak::One => Some(true),
+                TieBreak::KeepBoth => None,
+            },
+        }
+    }
+}
+
+// `most:1`, `least:0`, `most:both` and so on
+impl FromStr for BitCriterion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (common, tie) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Expected <most|least>:<0|1|both>, found {:?}", s))?;
+        let tie = match tie {
+            "0" => TieBreak::Zero,
+            "1" => TieBreak::One,
+            "both" => TieBreak::KeepBoth,
+            _ => return Err(format!("Unknown tie break: {:?}", tie)),
+        };
+        match common {
+            "most" => Ok(BitCriterion::MostCommon(tie)),
+            "least" => Ok(BitCriterion::LeastCommon(tie)),
+            _ => Err(format!("Unknown criterion: {:?}", common)),
+        }
+    }
+}
+
+impl fmt::Display for BitCriterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (common, tie) = match self {
+            BitCriterion::MostCommon(tie) => ("most", tie),
+            BitCriterion::LeastCommon(tie) => ("least", tie),
+        };
+        let tie = match tie {
+            TieBreak::Zero => "0",
+            TieBreak::One => "1",
+            TieBreak::KeepBoth => "both",
+        };
+        write!(f, "{}:{}", common, tie)
+    }
+}
+
+// a named rating search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rating {
+    pub name: &'static str,
+    pub criterion: BitCriterion,
+}
+
+pub const OXYGEN_GENERATOR: Rating = Rating {
+    name: "oxygen generator",
+    criterion: BitCriterion::MostCommon(TieBreak::One),
+};
+
+pub const CO2_SCRUBBER: Rating = Rating {
+    name: "CO2 scrubber",
+    criterion: BitCriterion::LeastCommon(TieBreak::Zero),
+};
+
+// the ratings whose product is the life support rating
+pub const LIFE_SUPPORT: [Rating; 2] = [OXYGEN_GENERATOR, CO2_SCRUBBER];
+
+impl Rating {
+    pub fn evaluate(&self, report: &Report) -> Result<Bits, RatingError> {
+        filter_report(report, self.criterion)
+    }
+}
+
+// the product of the ratings, or the first one that fails
+pub fn combined_rating(report: &Report, ratings: &[Rating]) -> Result<BigUint, RatingError> {
+    ratings
+        .iter()
+        .map(|rating| Ok(rating.evaluate(report)?.to_biguint()))
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example;
+
+    #[test]
+    fn keep() {
+        let most = BitCriterion::MostCommon(TieBreak::KeepBoth);
+        assert_eq!(most.keep(3, 5), Some(true));
+        assert_eq!(most.keep(5, 3), Some(false));
+        assert_eq!(most.keep(4, 4), None);
+        let least = BitCriterion::LeastCommon(TieBreak::One);
+        assert_eq!(least.keep(3, 5), Some(false));
+        assert_eq!(least.keep(4, 4), Some(true));
+        assert_eq!(least.keep(0, 2), Some(false));
+    }
+
+    #[test]
+    fn life_support() {
+        let report = example();
+        assert_eq!(
+            OXYGEN_GENERATOR.evaluate(&report).unwrap().to_u128(),
+            Some(23)
+        );
+        assert_eq!(CO2_SCRUBBER.evaluate(&report).unwrap().to_u128(), Some(10));
+        assert_eq!(combined_rating(&report, &LIFE_SUPPORT), Ok(230u32.into()));
+    }
+
+    #[test]
+    fn other_criteria() {
+        let report = example();
+        let rating = |criterion: &str| {
+            filter_report(&report, criterion.parse().unwrap()).map(|bits| bits.to_u128())
+        };
+        assert_eq!(rating("most:0"), Ok(Some(22)));
+        assert_eq!(rating("least:1"), Ok(Some(15)));
+        // keeping both on the tie in the last column leaves two rows
+        let survivors = ["10110", "10111"].map(|row| Report::parse(row).unwrap().row(0));
+        assert_eq!(
+            rating("most:both"),
+            Err(RatingError::Ambiguous {
+                survivors: survivors.to_vec()
+            })
+        );
+
+        // a tie in the first column, kept or broken
+        let report = Report::parse("010\n100\n101\n001\n").unwrap();
+        let rating = |criterion: &str| {
+            filter_report(&report, criterion.parse().unwrap()).map(|bits| bits.to_u128())
+        };
+        assert_eq!(rating("least:0"), Ok(Some(1)));
+        assert_eq!(rating("least:both"), Ok(Some(2)));
+
+        let report = example();
+        let custom = [
+            Rating {
+                name: "ballast",
+                criterion: "most:0".parse().unwrap(),
+            },
+            CO2_SCRUBBER,
+        ];
+        assert_eq!(combined_rating(&report, &custom), Ok((22u32 * 10).into()));
+    }
+
+    #[test]
+    fn parse() {
+        for criterion in ["most:0", "most:1", "least:both"] {
+            assert_eq!(
+                criterion.parse::<BitCriterion>().unwrap().to_string(),
+                criterion
+            );
+        }
+        assert!("most".parse::<BitCriterion>().is_err());
+        assert!("most:2".parse::<BitCriterion>().is_err());
+        assert!("mode:1".parse::<BitCriterion>().is_err());
+    }
+}
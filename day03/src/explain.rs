@@ -0,0 +1,191 @@
+use std::fmt::Write;
+
+use crate::criterion::{Rating, LIFE_SUPPORT};
+use crate::{rates, trace_report, Bits, RatingError, Report, Round};
+
+// the counts behind one bit of the gamma and epsilon rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnCounts {
+    pub num_zeros: usize,
+    pub num_ones: usize,
+    pub gamma: bool,
+    pub epsilon: bool,
+}
+
+// every round of a rating search and how it ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingTrace {
+    pub rating: Rating,
+    pub rows: usize,
+    pub rounds: Vec<Round>,
+    pub result: Result<Bits, RatingError>,
+}
+
+impl RatingTrace {
+    pub fn new(report: &Report, rating: Rating) -> Self {
+        let mut rounds = Vec::new();
+        let result = trace_report(report, rating.criterion, |round| rounds.push(round));
+        Self {
+            rating,
+            rows: report.len(),
+            rounds,
+            result,
+        }
+    }
+}
+
+// how the power consumption and the life support ratings come out of a
+// report, bit by bit, to track down answers that do not match
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub columns: Vec<ColumnCounts>,
+    pub gamma: Bits,
+    pub epsilon: Bits,
+    pub ratings: Vec<RatingTrace>,
+}
+
+impl Explanation {
+    pub fn new(report: &Report) -> Self {
+        let (gamma, epsilon) = rates(report);
+        let columns = (0..report.width())
+            .map(|col_idx| {
+                let (num_zeros, num_ones) = report.counts(col_idx);
+                ColumnCounts {
+                    num_zeros,
+                    num_ones,
+                    gamma: gamma.get(col_idx),
+                    epsilon: epsilon.get(col_idx),
+                }
+            })
+            .collect();
+        Self {
+            columns,
+            gamma,
+            epsilon,
+            ratings: LIFE_SUPPORT
+                .iter()
+                .map(|&rating| RatingTrace::new(report, rating))
+                .collect(),
+        }
+    }
+
+    // bits count from 1, most significant first
+    pub fn table(&self) -> String {
+        let bit = |b: bool| if b { "1" } else { "0" };
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{:>4} {:>7} {:>7} {:>5} {:>7}",
+            "bit", "zeros", "ones", "gamma", "epsilon"
+        )
+        .unwrap();
+        for (col_idx, c) in self.columns.iter().enumerate() {
+            writeln!(
+                out,
+                "{:>4} {:>7} {:>7} {:>5} {:>7}",
+                col_idx + 1,
+                c.num_zeros,
+                c.num_ones,
+                bit(c.gamma),
+                bit(c.epsilon)
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "gamma {} ({}), epsilon {} ({})",
+            self.gamma,
+            self.gamma.to_biguint(),
+            self.epsilon,
+            self.epsilon.to_biguint()
+        )
+        .unwrap();
+
+        for trace in &self.ratings {
+            writeln!(
+                out,
+                "\n{} rating ({}), {} rows:",
+                trace.rating.name, trace.rating.criterion, trace.rows
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{:>4} {:>7} {:>7} {:>5} {:>7}",
+                "bit", "zeros", "ones", "kept", "left"
+            )
+            .unwrap();
+            for round in &trace.rounds {
+                writeln!(
+                    out,
+                    "{:>4} {:>7} {:>7} {:>5} {:>7}",
+                    round.column + 1,
+                    round.num_zeros,
+                    round.num_ones,
+                    round.kept.map_or("both", bit),
+                    round.remaining
+                )
+                .unwrap();
+            }
+            match &trace.result {
+                Ok(row) => writeln!(out, "{} ({})", row, row.to_biguint()).unwrap(),
+                Err(err) => writeln!(out, "failed: {}", err).unwrap(),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example;
+
+    #[test]
+    fn example_explanation() {
+        let explanation = Explanation::new(&example());
+        assert_eq!(explanation.columns.len(), 5);
+        assert_eq!(
+            explanation.columns[0],
+            ColumnCounts {
+                num_zeros: 5,
+                num_ones: 7,
+                gamma: true,
+                epsilon: false,
+            }
+        );
+
+        let [oxygen, co2] = &explanation.ratings[..] else {
+            panic!("Expected two ratings.");
+        };
+        let left: Vec<usize> = oxygen.rounds.iter().map(|r| r.remaining).collect();
+        assert_eq!(left, [7, 4, 3, 2, 1]);
+        assert_eq!(
+            co2.rounds[2],
+            Round {
+                column: 2,
+                num_zeros: 1,
+                num_ones: 1,
+                kept: Some(false),
+                remaining: 1,
+            }
+        );
+        assert_eq!(co2.result.as_ref().unwrap().to_u128(), Some(10));
+
+        let table = explanation.table();
+        assert!(table.starts_with(
+            " bit   zeros    ones gamma epsilon\n   1       5       7     1       0\n"
+        ));
+        assert!(table.contains("gamma 10110 (22), epsilon 01001 (9)\n"));
+        assert!(table.contains("\noxygen generator rating (most:1), 12 rows:\n"));
+        assert!(table.contains("   5       1       1     1       1\n10111 (23)\n"));
+    }
+
+    #[test]
+    fn failed_rating() {
+        let explanation = Explanation::new(&Report::parse("11\n11\n").unwrap());
+        let table = explanation.table();
+        assert!(table.contains("failed: 2 rows left after the last column: 11 11\n"));
+        assert!(table
+            .contains("   1       0       2     0       0\nfailed: no rows left after column 1\n"));
+    }
+}
@@ -0,0 +1,573 @@
+use num_bigint::BigUint;
+use std::fmt;
+
+pub mod criterion;
+pub mod explain;
+pub mod trie;
+
+use criterion::{BitCriterion, CO2_SCRUBBER, LIFE_SUPPORT, OXYGEN_GENERATOR};
+
+// a row of the report, most significant bit first. bit `idx` is bit
+// `idx % 64` of word `idx / 64`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bits {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl Bits {
+    pub fn zeros(len: usize) -> Self {
+        Bits {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, idx: usize) -> bool {
+        assert!(idx < self.len, "bit {} out of {}", idx, self.len);
+        self.words[idx / 64] >> (idx % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, idx: usize, bit: bool) {
+        assert!(idx < self.len, "bit {} out of {}", idx, self.len);
+        let mask = 1 << (idx % 64);
+        if bit {
+            self.words[idx / 64] |= mask;
+        } else {
+            self.words[idx / 64] &= !mask;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|idx| self.get(idx))
+    }
+
+    // `None` if the value does not fit
+    pub fn to_u128(&self) -> Option<u128> {
+        let significant = self.iter().skip_while(|&bit| !bit).count();
+        if significant > 128 {
+            return None;
+        }
+        Some(self.iter().fold(0, |acc, bit| acc << 1 | bit as u128))
+    }
+
+    pub fn to_biguint(&self) -> BigUint {
+        self.iter().fold(BigUint::default(), |acc, bit| {
+            (acc << 1u8) + BigUint::from(bit as u8)
+        })
+    }
+}
+
+impl FromIterator<bool> for Bits {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = Bits::default();
+        for bit in iter {
+            if bits.len.is_multiple_of(64) {
+                bits.words.push(0);
+            }
+            bits.len += 1;
+            bits.set(bits.len - 1, bit);
+        }
+        bits
+    }
+}
+
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bit in self.iter() {
+            write!(f, "{}", if bit { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    // no rows, or a first row without bits
+    Empty,
+    // lines and columns are 1-based
+    InvalidChar {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    Ragged {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "the report is empty"),
+            ParseError::InvalidChar {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "line {}, column {}: expected 0 or 1, found {:?}",
+                line, column, found
+            ),
+            ParseError::Ragged {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "line {}: {} bits wide, expected {} like the first line",
+                line, width, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// why a rating search did not end with exactly one row
+#[derive(Debug, Clone, PartialEq)]
+pub enum RatingError {
+    // no rows to search
+    Empty,
+    // the criterion kept a bit no row left had, in this 0-based column
+    NoneLeft { column: usize },
+    // more than one row left after the last column, in report order
+    Ambiguous { survivors: Vec<Bits> },
+}
+
+impl fmt::Display for RatingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RatingError::Empty => write!(f, "the report is empty"),
+            RatingError::NoneLeft { column } => {
+                write!(f, "no rows left after column {}", column + 1)
+            }
+            RatingError::Ambiguous { survivors } => {
+                write!(f, "{} rows left after the last column:", survivors.len())?;
+                for row in survivors {
+                    write!(f, " {}", row)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for RatingError {}
+
+// a diagnostic report of rows that all have the same width, stored by
+// column: every column is a bitset over the rows, `stride` words long, with
+// the bits past the last row left 0. counting the ones in a column, or in
+// the part of it that survived a filter, is a popcount per 64 rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    width: usize,
+    len: usize,
+    stride: usize,
+    columns: Vec<u64>,
+}
+
+impl Report {
+    // the width is taken from the first line, every other line has to match
+    pub fn parse(input: &str) -> Result<Report, ParseError> {
+        let width = input.lines().next().map_or(0, |line| line.trim_end().len());
+        if width == 0 {
+            return Err(ParseError::Empty);
+        }
+        let len = input.lines().count();
+        let stride = len.div_ceil(64);
+        let mut columns = vec![0; width * stride];
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim_end();
+            let mut row_width = 0;
+            for (column, c) in line.chars().enumerate() {
+                let bit = match c {
+                    '0' => 0,
+                    '1' => 1,
+                    found => {
+                        return Err(ParseError::InvalidChar {
+                            line: idx + 1,
+                            column: column + 1,
+                            found,
+                        })
+                    }
+                };
+                if column < width {
+                    columns[column * stride + idx / 64] |= bit << (idx % 64);
+                }
+                row_width += 1;
+            }
+            if row_width != width {
+                return Err(ParseError::Ragged {
+                    line: idx + 1,
+                    width: row_width,
+                    expected: width,
+                });
+            }
+        }
+        Ok(Report {
+            width,
+            len,
+            stride,
+            columns,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // the number of rows
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // the rows with a 1 in the column
+    pub fn column(&self, col_idx: usize) -> &[u64] {
+        &self.columns[col_idx * self.stride..(col_idx + 1) * self.stride]
+    }
+
+    pub fn bit(&self, row_idx: usize, col_idx: usize) -> bool {
+        assert!(row_idx < self.len, "row {} out of {}", row_idx, self.len);
+        self.column(col_idx)[row_idx / 64] >> (row_idx % 64) & 1 == 1
+    }
+
+    pub fn row(&self, row_idx: usize) -> Bits {
+        (0..self.width)
+            .map(|col_idx| self.bit(row_idx, col_idx))
+            .collect()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = Bits> + '_ {
+        (0..self.len).map(|row_idx| self.row(row_idx))
+    }
+
+    // zeros and ones in a column
+    pub fn counts(&self, col_idx: usize) -> (usize, usize) {
+        let ones: usize = self
+            .column(col_idx)
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+        (self.len - ones, ones)
+    }
+
+    // a row mask, `stride` words long, with every row set
+    fn all_rows(&self) -> Vec<u64> {
+        let mut mask = vec![u64::MAX; self.stride];
+        if !self.len.is_multiple_of(64) {
+            mask[self.stride - 1] = (1 << (self.len % 64)) - 1;
+        }
+        mask
+    }
+}
+
+// one column of a rating search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Round {
+    // 0-based
+    pub column: usize,
+    // among the rows left before the round
+    pub num_zeros: usize,
+    pub num_ones: usize,
+    // `None` if both were kept
+    pub kept: Option<bool>,
+    // rows left after the round
+    pub remaining: usize,
+}
+
+// keep the rows whose bit in each column is the one the criterion picks
+// among the rows still left, until one remains. the surviving rows are a
+// bitset that is narrowed column by column, nothing is copied.
+pub fn filter_report(report: &Report, criterion: BitCriterion) -> Result<Bits, RatingError> {
+    trace_report(report, criterion, |_| {})
+}
+
+// `filter_report`, handing every round to `on_round` as it is done
+pub fn trace_report<F>(
+    report: &Report,
+    criterion: BitCriterion,
+    mut on_round: F,
+) -> Result<Bits, RatingError>
+where
+    F: FnMut(Round),
+{
+    if report.is_empty() {
+        return Err(RatingError::Empty);
+    }
+    let mut survivors = report.all_rows();
+    let mut remaining = report.len;
+    for col_idx in 0..report.width {
+        if remaining == 1 {
+            break;
+        }
+        let column = report.column(col_idx);
+        let num_ones: usize = survivors
+            .iter()
+            .zip(column)
+            .map(|(alive, ones)| (alive & ones).count_ones() as usize)
+            .sum();
+        let num_zeros = remaining - num_ones;
+        let kept = criterion.keep(num_zeros, num_ones);
+        match kept {
+            Some(true) => {
+                for (alive, ones) in survivors.iter_mut().zip(column) {
+                    *alive &= *ones;
+                }
+                remaining = num_ones;
+            }
+            Some(false) => {
+                for (alive, ones) in survivors.iter_mut().zip(column) {
+                    *alive &= !*ones;
+                }
+                remaining -= num_ones;
+            }
+            None => {}
+        }
+        on_round(Round {
+            column: col_idx,
+            num_zeros,
+            num_ones,
+            kept,
+            remaining,
+        });
+        if remaining == 0 {
+            return Err(RatingError::NoneLeft { column: col_idx });
+        }
+    }
+
+    let mut survivors: Vec<Bits> = (0..report.len)
+        .filter(|&row_idx| survivors[row_idx / 64] >> (row_idx % 64) & 1 == 1)
+        .map(|row_idx| report.row(row_idx))
+        .collect();
+    match survivors.len() {
+        1 => Ok(survivors.pop().unwrap()),
+        _ => Err(RatingError::Ambiguous { survivors }),
+    }
+}
+
+pub fn oxygen_generator_rating(report: &Report) -> Result<Bits, RatingError> {
+    OXYGEN_GENERATOR.evaluate(report)
+}
+
+pub fn co2_scrubber_rating(report: &Report) -> Result<Bits, RatingError> {
+    CO2_SCRUBBER.evaluate(report)
+}
+
+// gamma and epsilon rates, most and least common bits
+pub fn rates(report: &Report) -> (Bits, Bits) {
+    let gamma: Bits = (0..report.width)
+        .map(|col_idx| {
+            let (num_zeros, num_ones) = report.counts(col_idx);
+            num_zeros <= num_ones
+        })
+        .collect();
+    let epsilon = gamma.iter().map(|bit| !bit).collect();
+    (gamma, epsilon)
+}
+
+pub fn power_consumption(report: &Report) -> BigUint {
+    let (gamma, epsilon) = rates(report);
+    gamma.to_biguint() * epsilon.to_biguint()
+}
+
+pub fn life_support_rating(report: &Report) -> Result<BigUint, RatingError> {
+    criterion::combined_rating(report, &LIFE_SUPPORT)
+}
+
+// the puzzle's example report
+#[cfg(test)]
+pub(crate) fn example() -> Report {
+    Report::parse(
+        "00100\n\
+         11110\n\
+         10110\n\
+         10111\n\
+         10101\n\
+         01111\n\
+         00111\n\
+         11100\n\
+         10000\n\
+         11001\n\
+         00010\n\
+         01010\n",
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    #[test]
+    fn test_part1() {
+        assert_eq!(power_consumption(&example()), 198u32.into());
+    }
+    #[test]
+    fn test_part2_oxygen() {
+        assert_eq!(
+            oxygen_generator_rating(&example()).unwrap().to_u128(),
+            Some(23)
+        );
+    }
+    #[test]
+    fn test_part2_co2() {
+        assert_eq!(co2_scrubber_rating(&example()).unwrap().to_u128(), Some(10));
+        assert_eq!(life_support_rating(&example()), Ok(230u32.into()));
+    }
+
+    #[test]
+    fn wide_report() {
+        // every row of the example repeated side by side
+        let widen = |times: usize| {
+            let rows: String = example()
+                .rows()
+                .map(|row| format!("{}\n", row.to_string().repeat(times)))
+                .collect();
+            Report::parse(&rows).unwrap()
+        };
+        let binary = |bits: &str, times: usize| {
+            BigUint::parse_bytes(bits.repeat(times).as_bytes(), 2).unwrap()
+        };
+
+        let report = widen(29);
+        assert_eq!(report.width(), 145);
+        let oxygen = oxygen_generator_rating(&report).unwrap();
+        assert_eq!(oxygen.to_string(), "10111".repeat(29));
+        assert_eq!(oxygen.to_u128(), None);
+        assert_eq!(oxygen.to_biguint(), binary("10111", 29));
+        assert_eq!(
+            co2_scrubber_rating(&report).unwrap().to_string(),
+            "01010".repeat(29)
+        );
+        assert_eq!(
+            power_consumption(&report),
+            binary("10110", 29) * binary("01001", 29)
+        );
+
+        // 125 bits still fit
+        let report = widen(25);
+        let oxygen = oxygen_generator_rating(&report).unwrap().to_u128().unwrap();
+        assert_eq!(BigUint::from(oxygen), binary("10111", 25));
+        let co2 = co2_scrubber_rating(&report).unwrap().to_u128().unwrap();
+        assert_eq!(BigUint::from(co2), binary("01010", 25));
+
+        // leading zeros do not count
+        let bits: Bits = std::iter::once(false)
+            .chain(std::iter::repeat_n(true, 128))
+            .collect();
+        assert_eq!(bits.to_u128(), Some(u128::MAX));
+    }
+
+    #[test]
+    fn many_rows() {
+        // every 17 bit value once, so every column and every round is a tie
+        let input: String = (0..1u32 << 17).map(|v| format!("{:017b}\n", v)).collect();
+        let report = Report::parse(&input).unwrap();
+        assert_eq!(report.len(), 1 << 17);
+        assert_eq!(report.counts(0), (1 << 16, 1 << 16));
+        assert_eq!(report.row(12345).to_u128(), Some(12345));
+        assert_eq!(
+            oxygen_generator_rating(&report).unwrap().to_u128(),
+            Some((1 << 17) - 1)
+        );
+        assert_eq!(co2_scrubber_rating(&report).unwrap().to_u128(), Some(0));
+
+        // rows that do not fill the last word
+        let report = Report::parse(&input[..18 * 100]).unwrap();
+        assert_eq!(report.counts(16), (50, 50));
+        assert_eq!(report.rows().last().unwrap().to_u128(), Some(99));
+        // 0 to 63 outnumber 64 to 99, then every round is a tie
+        assert_eq!(
+            oxygen_generator_rating(&report).unwrap().to_u128(),
+            Some(63)
+        );
+    }
+
+    #[test]
+    fn degenerate_reports() {
+        // a single row is its own rating
+        let report = Report::parse("0110\n").unwrap();
+        assert_eq!(oxygen_generator_rating(&report).unwrap().to_u128(), Some(6));
+        assert_eq!(co2_scrubber_rating(&report).unwrap().to_u128(), Some(6));
+
+        // every row the same: the least common bit is one nobody has
+        let report = Report::parse("101\n101\n").unwrap();
+        assert_eq!(
+            co2_scrubber_rating(&report),
+            Err(RatingError::NoneLeft { column: 0 })
+        );
+        let err = oxygen_generator_rating(&report).unwrap_err();
+        assert_eq!(
+            err,
+            RatingError::Ambiguous {
+                survivors: report.rows().collect()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "2 rows left after the last column: 101 101"
+        );
+        assert!(life_support_rating(&report).is_err());
+
+        let empty = Report {
+            width: 3,
+            len: 0,
+            stride: 0,
+            columns: Vec::new(),
+        };
+        assert_eq!(oxygen_generator_rating(&empty), Err(RatingError::Empty));
+    }
+
+    #[test]
+    fn bits() {
+        let mut bits: Bits = [true, false, true].into_iter().collect();
+        bits.set(1, true);
+        bits.set(0, false);
+        assert_eq!(bits.to_string(), "011");
+        let wide: Bits = (0..200).map(|idx| idx % 3 == 0).collect();
+        assert_eq!(wide.len(), 200);
+        assert!(wide.get(198) && !wide.get(199));
+        assert_eq!(Bits::zeros(70).to_u128(), Some(0));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Report::parse(""), Err(ParseError::Empty));
+        assert_eq!(Report::parse("\n0101"), Err(ParseError::Empty));
+        assert_eq!(
+            Report::parse("0101\n011\n0110"),
+            Err(ParseError::Ragged {
+                line: 2,
+                width: 3,
+                expected: 4
+            })
+        );
+        let err = Report::parse("0101\n01\n0120").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: 2 bits wide, expected 4 like the first line"
+        );
+        let err = Report::parse("0101\n0120").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 3: expected 0 or 1, found '2'"
+        );
+        // trailing whitespace and line endings are fine
+        assert_eq!(Report::parse("01\r\n10 \n").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::criterion::{BitCriterion, CO2_SCRUBBER, OXYGEN_GENERATOR};
+use crate::{Bits, RatingError, Report};
+
+// the rows of a report as a binary trie, most significant bit at the root,
+// where every node counts the rows below it. a rating is a single walk from
+// the root, so once the trie is built every rating takes time proportional to
+// the width rather than the number of rows. building it costs far more than
+// filtering the packed report once (see `benches/ratings.rs`), so it only
+// pays off when many ratings are taken from the same report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trie {
+    width: usize,
+    // the root is node 0, so 0 doubles as "no child"
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Node {
+    children: [usize; 2],
+    count: usize,
+    parent: usize,
+    // the bit on the edge from the parent
+    bit: bool,
+}
+
+impl Trie {
+    pub fn new(report: &Report) -> Self {
+        let mut nodes = vec![Node::default()];
+        for row_idx in 0..report.len() {
+            let mut node = 0;
+            nodes[node].count += 1;
+            for col_idx in 0..report.width() {
+                let bit = report.bit(row_idx, col_idx);
+                if nodes[node].children[bit as usize] == 0 {
+                    nodes.push(Node {
+                        parent: node,
+                        bit,
+                        ..Node::default()
+                    });
+                    nodes[node].children[bit as usize] = nodes.len() - 1;
+                }
+                node = nodes[node].children[bit as usize];
+                nodes[node].count += 1;
+            }
+        }
+        Trie {
+            width: report.width(),
+            nodes,
+        }
+    }
+
+    fn count(&self, node: usize, bit: bool) -> usize {
+        match self.nodes[node].children[bit as usize] {
+            0 => 0,
+            child => self.nodes[child].count,
+        }
+    }
+
+    // the row a leaf stands for
+    fn row(&self, mut node: usize) -> Bits {
+        let mut bits = Bits::zeros(self.width);
+        for col_idx in (0..self.width).rev() {
+            bits.set(col_idx, self.nodes[node].bit);
+            node = self.nodes[node].parent;
+        }
+        bits
+    }
+
+    // the same rating as `filter_report` with the same criterion. the rows
+    // left are the subtrees of a set of nodes at the same depth, which is a
+    // single node unless the criterion keeps both bits on a tie. the
+    // survivors of an ambiguous search come in numeric order, not report
+    // order, which the trie does not keep.
+    pub fn rating(&self, criterion: BitCriterion) -> Result<Bits, RatingError> {
+        if self.nodes[0].count == 0 {
+            return Err(RatingError::Empty);
+        }
+        let mut frontier = vec![0];
+        for col_idx in 0..self.width {
+            if let [node] = frontier[..] {
+                if self.nodes[node].count == 1 {
+                    break;
+                }
+            }
+            let num_zeros: usize = frontier.iter().map(|&node| self.count(node, false)).sum();
+            let num_ones: usize = frontier.iter().map(|&node| self.count(node, true)).sum();
+            let sides: &[bool] = match criterion.keep(num_zeros, num_ones) {
+                Some(false) => &[false],
+                Some(true) => &[true],
+                None => &[false, true],
+            };
+            frontier = frontier
+                .iter()
+                .flat_map(|&node| {
+                    sides
+                        .iter()
+                        .map(move |&bit| self.nodes[node].children[bit as usize])
+                })
+                .filter(|&child| child != 0)
+                .collect();
+            if frontier.is_empty() {
+                return Err(RatingError::NoneLeft { column: col_idx });
+            }
+        }
+
+        let mut survivors = Vec::new();
+        for node in frontier {
+            self.leaves(node, &mut survivors);
+        }
+        match survivors.len() {
+            1 => Ok(survivors.pop().unwrap()),
+            _ => Err(RatingError::Ambiguous { survivors }),
+        }
+    }
+
+    // every row below a node, zeros first, once for each time it is in the
+    // report
+    fn leaves(&self, node: usize, rows: &mut Vec<Bits>) {
+        match self.nodes[node].children {
+            [0, 0] => {
+                let row = self.row(node);
+                rows.extend(std::iter::repeat_n(row, self.nodes[node].count));
+            }
+            children => {
+                for child in children.into_iter().filter(|&child| child != 0) {
+                    self.leaves(child, rows);
+                }
+            }
+        }
+    }
+
+    pub fn oxygen_generator_rating(&self) -> Result<Bits, RatingError> {
+        self.rating(OXYGEN_GENERATOR.criterion)
+    }
+
+    pub fn co2_scrubber_rating(&self) -> Result<Bits, RatingError> {
+        self.rating(CO2_SCRUBBER.criterion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{example, filter_report};
+
+    const CRITERIA: [&str; 6] = [
+        "most:0",
+        "most:1",
+        "most:both",
+        "least:0",
+        "least:1",
+        "least:both",
+    ];
+
+    #[test]
+    fn example_ratings() {
+        let trie = Trie::new(&example());
+        assert_eq!(trie.oxygen_generator_rating().unwrap().to_u128(), Some(23));
+        assert_eq!(trie.co2_scrubber_rating().unwrap().to_u128(), Some(10));
+    }
+
+    #[test]
+    fn same_as_filtering() {
+        // pseudo random reports, xorshift
+        let mut state = 0x2545f4914f6cdd1du64;
+        for rows in [1, 2, 3, 10, 65, 200] {
+            let input: String = (0..rows)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    format!("{:012b}\n", state >> 52)
+                })
+                .collect();
+            let report = Report::parse(&input).unwrap();
+            let trie = Trie::new(&report);
+            for criterion in CRITERIA {
+                let criterion = criterion.parse().unwrap();
+                let mut expected = filter_report(&report, criterion);
+                if let Err(RatingError::Ambiguous { survivors }) = &mut expected {
+                    survivors.sort_by_key(|row| row.to_u128());
+                }
+                let actual = trie.rating(criterion);
+                assert_eq!(actual, expected, "{} rows, {}", rows, criterion);
+            }
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt::Debug;
+
+pub mod scoring;
+pub mod stream;
+pub mod syntax;
+
+use scoring::Scoring;
+use syntax::{Lexeme, Lexer, Syntax};
+
+// scoring for part 1
+pub fn score(state: State) -> usize {
+    Scoring::default().corruption(&state)
+}
+
+// the bracket pairs of the puzzle, opener first
+pub const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+// map character to its counterpart
+pub fn reverse(c: char) -> Option<char> {
+    match c {
+        '>' => Some('<'),
+        '}' => Some('{'),
+        ']' => Some('['),
+        ')' => Some('('),
+        '<' => Some('>'),
+        '{' => Some('}'),
+        '[' => Some(']'),
+        '(' => Some(')'),
+        _ => None,
+    }
+}
+
+// calculate points for a completion (pt2), `None` if it contains anything
+// but closers
+pub fn points(chars: Vec<char>) -> Option<u128> {
+    Scoring::default().completion(&chars)
+}
+
+#[derive(Debug, std::cmp::PartialEq)]
+pub enum State {
+    Valid,
+    Incomplete,
+    Corrupted(char),
+    // 0-based position in the line and the offending character
+    InvalidChar(usize, char),
+}
+
+// what to do with characters that are not one of the eight brackets
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum InvalidCharPolicy {
+    // drop them entirely, they do not count towards positions
+    Ignore,
+    // stop checking the line with `State::InvalidChar`
+    #[default]
+    Error,
+    // step over them like text, they do count towards positions
+    Text,
+}
+
+// stack of currently open brackets, fed one character at a time
+#[derive(Debug)]
+pub struct Matcher {
+    stack: Vec<char>,
+    // (opener, closer)
+    pairs: Vec<(char, char)>,
+    policy: InvalidCharPolicy,
+    lexer: Lexer,
+    position: usize,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            pairs: PAIRS.to_vec(),
+            policy: InvalidCharPolicy::default(),
+            lexer: Lexer::default(),
+            position: 0,
+        }
+    }
+}
+
+impl Matcher {
+    pub fn new(policy: InvalidCharPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    // skip brackets inside the strings and comments of `syntax`
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.lexer = Lexer::new(syntax);
+        self
+    }
+
+    // match these pairs instead of the puzzle's, e.g. the ones of a
+    // scoring table
+    pub fn with_pairs(mut self, pairs: Vec<(char, char)>) -> Self {
+        self.pairs = pairs;
+        self
+    }
+
+    // a closer that does not match the popped opener, or an invalid
+    // character under `InvalidCharPolicy::Error`, ends the line with the
+    // returned state
+    pub fn feed(&mut self, c: char) -> Result<(), State> {
+        self.lexer.feed(c);
+        self.drain()
+    }
+
+    // end of line; returns the state of everything fed since the last reset
+    pub fn finish(&mut self) -> State {
+        self.lexer.finish();
+        if let Err(state) = self.drain() {
+            return state;
+        }
+        if self.stack.is_empty() && self.lexer.closer().is_none() {
+            return State::Valid;
+        }
+        State::Incomplete
+    }
+
+    fn drain(&mut self) -> Result<(), State> {
+        while let Some(lexeme) = self.lexer.next_lexeme() {
+            match lexeme {
+                Lexeme::Code(c) => self.code(c)?,
+                Lexeme::Text => self.position += 1,
+            }
+        }
+        Ok(())
+    }
+
+    // push an opener or pop for a closer
+    fn code(&mut self, c: char) -> Result<(), State> {
+        let position = self.position;
+        if self.pairs.iter().any(|&(opener, _)| opener == c) {
+            self.stack.push(c);
+        } else if self.pairs.iter().any(|&(_, closer)| closer == c) {
+            match self.stack.pop() {
+                Some(opener) if !self.pairs.contains(&(opener, c)) => {
+                    self.position += 1;
+                    return Err(State::Corrupted(c));
+                }
+                _ => {}
+            }
+        } else {
+            match self.policy {
+                InvalidCharPolicy::Ignore => return Ok(()),
+                InvalidCharPolicy::Text => {}
+                InvalidCharPolicy::Error => {
+                    self.position += 1;
+                    return Err(State::InvalidChar(position, c));
+                }
+            }
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    // number of characters counted so far, i.e. the 1-based column of the
+    // last one
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // closers needed to balance the input fed so far, starting with the
+    // delimiter of an unterminated string or block comment
+    pub fn completion(&self) -> Vec<char> {
+        let closer = self.lexer.closer().unwrap_or_default();
+        let brackets = self.stack.iter().rev().filter_map(|&c| {
+            let (_, closer) = self.pairs.iter().find(|&&(opener, _)| opener == c)?;
+            Some(*closer)
+        });
+        closer.chars().chain(brackets).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.lexer.reset();
+        self.position = 0;
+    }
+}
+
+pub fn evaluate(line: &str) -> State {
+    evaluate_with(line, &mut Matcher::default())
+}
+
+pub fn evaluate_with(line: &str, matcher: &mut Matcher) -> State {
+    matcher.reset();
+    for char in line.chars() {
+        if let Err(state) = matcher.feed(char) {
+            return state;
+        }
+    }
+    matcher.finish()
+}
+
+pub fn complete(line: &str) -> Vec<char> {
+    complete_with(line, &mut Matcher::default())
+}
+
+pub fn complete_with(line: &str, matcher: &mut Matcher) -> Vec<char> {
+    matcher.reset();
+    for char in line.chars() {
+        // mismatched closers and invalid characters are stepped over
+        let _ = matcher.feed(char);
+    }
+    matcher.finish();
+    matcher.completion()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn valid_lines() {
+        let lines = [
+            "([])",
+            "{()()()}",
+            "<([{}])>",
+            "[<>({}){}[([])<>]]",
+            "(((((((((())))))))))",
+        ];
+
+        for line in lines {
+            assert_eq!(evaluate(line), State::Valid);
+        }
+    }
+    #[test]
+    fn incomplete_lines() {
+        let lines = [
+            "([]",
+            "{()()()",
+            "<([{}])",
+            "[<>({}){}[([])<>]",
+            "(((((((((()))))))))",
+        ];
+
+        for line in lines {
+            assert_eq!(evaluate(line), State::Incomplete);
+        }
+    }
+
+    #[test]
+    fn corrupted_lines() {
+        let lines = [
+            ("(]", State::Corrupted(']')),
+            ("{()()()>", State::Corrupted('>')),
+            ("(((()))}", State::Corrupted('}')),
+            ("<([]){()}[{}])", State::Corrupted(')')),
+        ];
+
+        for (line, expected) in lines {
+            assert_eq!(evaluate(line), expected);
+        }
+    }
+
+    #[test]
+    fn test_complete() {
+        let lines_and_completions = [
+            ("[({(<(())[]>[[{[]{<()<>>", "}}]])})]"),
+            ("[(()[<>])]({[<{<<[]>>(", ")}>]})"),
+            ("(((({<>}<{<{<>}{[]{[]{}", "}}>}>))))"),
+            ("{<[[]]>}<{[{[{[]{()[[[]", "]]}}]}]}>"),
+            ("<{([{{}}[<[[[<>{}]]]>[]]", "])}>"),
+        ];
+
+        for (line, completion) in lines_and_completions {
+            let completed: String = complete(line).iter().collect();
+            assert_eq!(completed, completion);
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        let lines_and_expected = [
+            ("[({(<(())[]>[[{[]{<()<>>", State::Incomplete),
+            ("[(()[<>])]({[<{<<[]>>(", State::Incomplete),
+            ("{([(<{}[<>[]}>{[]{[(<()>", State::Corrupted('}')),
+            ("(((({<>}<{<{<>}{[]{[]{}", State::Incomplete),
+            ("[[<[([]))<([[{}[[()]]]", State::Corrupted(')')),
+            ("[{[{({}]{}}([{[{{{}}([]", State::Corrupted(']')),
+            ("{<[[]]>}<{[{[{[]{()[[[]", State::Incomplete),
+            ("[<(<(<(<{}))><([]([]()", State::Corrupted(')')),
+            ("<{([([[(<>()){}]>(<<{{", State::Corrupted('>')),
+            ("<{([{{}}[<[[[<>{}]]]>[]]", State::Incomplete),
+        ];
+
+        for (line, expected) in &lines_and_expected {
+            assert_eq!(evaluate(line), *expected);
+        }
+
+        let lines = lines_and_expected.iter().map(|(l, _)| *l);
+        let total_error_score: usize = lines.into_iter().map(evaluate).map(score).sum();
+        assert_eq!(total_error_score, 26397);
+    }
+
+    #[test]
+    fn invalid_chars() {
+        let line = "{a [\r]) >\r";
+        assert_eq!(evaluate(line), State::InvalidChar(1, 'a'));
+        let mut ignore = Matcher::new(InvalidCharPolicy::Ignore);
+        assert_eq!(evaluate_with(line, &mut ignore), State::Corrupted(')'));
+        assert_eq!(ignore.position(), 4);
+        let mut text = Matcher::new(InvalidCharPolicy::Text);
+        assert_eq!(evaluate_with(line, &mut text), State::Corrupted(')'));
+        assert_eq!(text.position(), 7);
+
+        assert_eq!(evaluate_with("{ x\r", &mut text), State::Incomplete);
+        assert_eq!(complete_with("{ ( x ]\r", &mut text), vec!['}']);
+        assert_eq!(complete("{ ( x"), vec![')', '}']);
+    }
+
+    #[test]
+    fn invalid_completion_points() {
+        assert_eq!(
+            points(vec!['}', '}', ']', ']', ')', '}', ')', ']']),
+            Some(288957)
+        );
+        assert_eq!(points(vec![')', 'x']), None);
+        assert_eq!(reverse('x'), None);
+    }
+
+    #[test]
+    fn brackets_in_literals() {
+        let mut matcher = Matcher::new(InvalidCharPolicy::Text).with_syntax(Syntax::c_like());
+        let snippets = [
+            (r#"f("(", ']');"#, State::Valid),
+            (r#"s = "\")" + '['; // )"#, State::Valid),
+            ("if (a /* ) */ && b) { x[0] = '}'", State::Incomplete),
+            ("g(a / b)", State::Valid),
+            (r#"f(vec!["]", "<"])"#, State::Valid),
+            ("{ ( /* ] */ ] }", State::Corrupted(']')),
+            (r#"{ "unterminated ]"#, State::Incomplete),
+            ("( /* open", State::Incomplete),
+        ];
+        for (snippet, expected) in snippets {
+            assert_eq!(
+                evaluate_with(snippet, &mut matcher),
+                expected,
+                "{}",
+                snippet
+            );
+        }
+
+        assert_eq!(
+            complete_with("if (a /* ) */ && b) { x[0] = '}'", &mut matcher),
+            vec!['}']
+        );
+        let completed: String = complete_with(r#"{ "unterminated ]"#, &mut matcher)
+            .iter()
+            .collect();
+        assert_eq!(completed, "\"}");
+        let completed: String = complete_with("( /* open", &mut matcher).iter().collect();
+        assert_eq!(completed, "*/)");
+    }
+
+    #[test]
+    fn positions_count_literals() {
+        let mut matcher = Matcher::new(InvalidCharPolicy::Error).with_syntax(Syntax::c_like());
+        assert_eq!(
+            evaluate_with("('x' // é", &mut matcher),
+            State::InvalidChar(4, ' ')
+        );
+        assert_eq!(
+            evaluate_with("('é')/", &mut matcher),
+            State::InvalidChar(5, '/')
+        );
+        assert_eq!(evaluate_with("('é')//)", &mut matcher), State::Valid);
+        assert_eq!(matcher.position(), 8);
+    }
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::State;
+
+// points for one bracket pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub opener: char,
+    pub closer: char,
+    // added to the syntax error score when the closer corrupts a line
+    pub corruption: usize,
+    // digit of the completion score for the closer
+    pub completion: u128,
+}
+
+// scoring of corrupted and incomplete lines for a set of bracket pairs.
+//
+// a table can be read from a file with one `opener closer corruption
+// completion` row per pair and a `base` line for the completion multiplier,
+// e.g. `( ) 3 1` and `base 5`. blank lines and `#` comments are skipped.
+// the pairs need not be the puzzle's, a `Matcher` can be built from them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scoring {
+    pub entries: Vec<Entry>,
+    pub base: u128,
+}
+
+impl Default for Scoring {
+    // the scoring of the puzzle
+    fn default() -> Self {
+        let entry = |opener, closer, corruption, completion| Entry {
+            opener,
+            closer,
+            corruption,
+            completion,
+        };
+        Self {
+            entries: vec![
+                entry('(', ')', 3, 1),
+                entry('[', ']', 57, 2),
+                entry('{', '}', 1197, 3),
+                entry('<', '>', 25137, 4),
+            ],
+            base: 5,
+        }
+    }
+}
+
+impl Scoring {
+    pub fn load<P>(path: P) -> Result<Self, ScoringError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|err| ScoringError {
+                line: 0,
+                message: err.to_string(),
+            })?;
+        contents.parse()
+    }
+
+    // (opener, closer) for `Matcher::with_pairs`
+    pub fn pairs(&self) -> Vec<(char, char)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.opener, entry.closer))
+            .collect()
+    }
+
+    fn entry(&self, closer: char) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.closer == closer)
+    }
+
+    // syntax error score of a line (pt1)
+    pub fn corruption(&self, state: &State) -> usize {
+        match state {
+            State::Corrupted(c) => self.entry(*c).map_or(0, |entry| entry.corruption),
+            _ => 0,
+        }
+    }
+
+    // score of a completion (pt2), `None` if it contains a character without
+    // an entry or the score overflows. with base 5 that takes more than 55
+    // closers.
+    pub fn completion(&self, chars: &[char]) -> Option<u128> {
+        chars.iter().try_fold(0u128, |score, &c| {
+            let value = self.entry(c)?.completion;
+            score.checked_mul(self.base)?.checked_add(value)
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ScoringError {
+    // 1-based line of the table, 0 if the file could not be read
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScoringError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "scoring table line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScoringError {}
+
+impl FromStr for Scoring {
+    type Err = ScoringError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut base = None;
+        for (idx, line) in s.lines().enumerate() {
+            let error = |message: String| ScoringError {
+                line: idx + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |field: &str| {
+                field
+                    .parse::<usize>()
+                    .map_err(|_| error(format!("Could not parse number {:?}.", field)))
+            };
+            match fields[..] {
+                [] => {}
+                ["base", value] => base = Some(number(value)? as u128),
+                [opener, closer, corruption, completion] => {
+                    let (opener, closer) = match (single(opener), single(closer)) {
+                        (Some(o), Some(c)) if o != c => (o, c),
+                        _ => {
+                            return Err(error(format!("Not a bracket pair: {} {}", opener, closer)))
+                        }
+                    };
+                    // every bracket has to be either an opener or a closer
+                    let taken = |c: char| {
+                        entries
+                            .iter()
+                            .any(|entry| entry.opener == c || entry.closer == c)
+                    };
+                    if let Some(c) = [opener, closer].into_iter().find(|&c| taken(c)) {
+                        return Err(error(format!("Duplicate entry for {}", c)));
+                    }
+                    entries.push(Entry {
+                        opener,
+                        closer,
+                        corruption: number(corruption)?,
+                        completion: number(completion)? as u128,
+                    });
+                }
+                _ => return Err(error(format!("Unexpected line: {:?}", line.trim()))),
+            }
+        }
+        let base = base.ok_or_else(|| ScoringError {
+            line: 0,
+            message: "Missing base.".to_string(),
+        })?;
+        Ok(Self { entries, base })
+    }
+}
+
+fn single(field: &str) -> Option<char> {
+    let mut chars = field.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{evaluate_with, Matcher};
+
+    #[test]
+    fn parse_table() {
+        let table = "# puzzle scoring\n\
+                     base 5\n\
+                     ( ) 3 1\n\
+                     [ ] 57 2\n\
+                     \n\
+                     { } 1197 3 # braces\n\
+                     < > 25137 4\n";
+        assert_eq!(table.parse::<Scoring>(), Ok(Scoring::default()));
+    }
+
+    #[test]
+    fn alternate_scoring() {
+        let scoring: Scoring = "base 10\n( ) 1 1\n[ ] 2 2\n".parse().unwrap();
+        assert_eq!(scoring.corruption(&State::Corrupted(']')), 2);
+        assert_eq!(scoring.corruption(&State::Corrupted('}')), 0);
+        assert_eq!(scoring.completion(&[']', ')', ']']), Some(212));
+        assert_eq!(scoring.completion(&['}']), None);
+    }
+
+    #[test]
+    fn table_pairs() {
+        let scoring: Scoring = "base 3\n( ) 1 1\n| / 5 2\n".parse().unwrap();
+        let mut matcher = Matcher::default().with_pairs(scoring.pairs());
+        assert_eq!(evaluate_with("(|/)", &mut matcher), State::Valid);
+        assert_eq!(evaluate_with("|()", &mut matcher), State::Incomplete);
+        assert_eq!(matcher.completion(), vec!['/']);
+        assert_eq!(evaluate_with("(|)", &mut matcher), State::Corrupted(')'));
+        assert_eq!(
+            evaluate_with("([])", &mut matcher),
+            State::InvalidChar(1, '[')
+        );
+        assert_eq!(scoring.corruption(&State::Corrupted('/')), 5);
+    }
+
+    #[test]
+    fn completion_overflow() {
+        let scoring = Scoring::default();
+        // 55 closers worth 4 add up to 5^55 - 1, which still fits
+        assert_eq!(scoring.completion(&['>'; 55]), Some(5u128.pow(55) - 1));
+        assert_eq!(scoring.completion(&['>'; 56]), None);
+    }
+
+    #[test]
+    fn invalid_tables() {
+        let err = |s: &str| s.parse::<Scoring>().unwrap_err();
+        assert_eq!(err("( ) 3 1").message, "Missing base.");
+        assert_eq!(err("base 5\n(( ) 3 1").line, 2);
+        assert_eq!(
+            err("base 5\n( ) 3 x").message,
+            "Could not parse number \"x\"."
+        );
+        assert_eq!(err("base 5\n( ) 3 1\n( ) 4 2").line, 3);
+        assert_eq!(err("base 5\n( ) 3 1\n) ] 4 2").line, 3);
+        assert_eq!(err("base 5\n| | 3 1").line, 2);
+        assert_eq!(err("base\n").line, 1);
+    }
+}
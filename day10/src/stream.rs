@@ -0,0 +1,245 @@
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufReader, Bytes};
+
+use crate::{InvalidCharPolicy, Matcher, State};
+
+// verdict for a single line of a streamed input
+#[derive(Debug, PartialEq)]
+pub struct LineReport {
+    // 1-based line number
+    pub line: usize,
+    // 1-based column at which the verdict was reached, i.e. the offending
+    // character for corrupted lines and the line length otherwise. ignored
+    // characters are not counted.
+    pub column: usize,
+    // nesting depth after that column
+    pub depth: usize,
+    pub state: State,
+    // closers needed to balance the line, empty unless incomplete
+    pub completion: Vec<char>,
+}
+
+// validates bracket lines read from any `Read`, one character at a time.
+// only the stack of open brackets is kept in memory, so lines of any
+// length can be checked.
+pub struct Validator<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    matcher: Matcher,
+    line: usize,
+    done: bool,
+}
+
+impl<R: Read> Validator<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_policy(reader, InvalidCharPolicy::default())
+    }
+
+    pub fn with_policy(reader: R, policy: InvalidCharPolicy) -> Self {
+        Self::with_matcher(reader, Matcher::new(policy))
+    }
+
+    pub fn with_matcher(reader: R, matcher: Matcher) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            matcher,
+            line: 0,
+            done: false,
+        }
+    }
+
+    // decode the next utf-8 character, `None` at end of input
+    fn next_char(&mut self) -> io::Result<Option<char>> {
+        let first = match self.bytes.next() {
+            Some(byte) => byte?,
+            None => return Ok(None),
+        };
+        let len = match first {
+            0x00..=0x7f => return Ok(Some(first as char)),
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Err(invalid_utf8()),
+        };
+        let mut buf = [first, 0, 0, 0];
+        for slot in buf.iter_mut().take(len).skip(1) {
+            *slot = match self.bytes.next() {
+                Some(byte) => byte?,
+                None => return Err(invalid_utf8()),
+            };
+        }
+        match std::str::from_utf8(&buf[..len]) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(invalid_utf8()),
+        }
+    }
+
+    // throw away the rest of a line after its verdict has been reached
+    fn skip_line(&mut self) -> io::Result<()> {
+        while let Some(c) = self.next_char()? {
+            if c == '\n' {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<LineReport>> {
+        self.matcher.reset();
+        let mut empty = true;
+        loop {
+            let c = match self.next_char()? {
+                Some(c) => c,
+                // like `str::lines`, no trailing empty line at the end
+                None if empty => return Ok(None),
+                None => break,
+            };
+            empty = false;
+            if c == '\n' {
+                break;
+            }
+            if let Err(state) = self.matcher.feed(c) {
+                let report = LineReport {
+                    line: self.line + 1,
+                    column: self.matcher.position(),
+                    depth: self.matcher.depth(),
+                    state,
+                    completion: Vec::new(),
+                };
+                self.line += 1;
+                self.skip_line()?;
+                return Ok(Some(report));
+            }
+        }
+        self.line += 1;
+        let state = self.matcher.finish();
+        let completion = match state {
+            State::Incomplete => self.matcher.completion(),
+            _ => Vec::new(),
+        };
+        Ok(Some(LineReport {
+            line: self.line,
+            column: self.matcher.position(),
+            depth: self.matcher.depth(),
+            state,
+            completion,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for Validator<R> {
+    type Item = io::Result<LineReport>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_line();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::evaluate;
+
+    fn validate(input: &str) -> Vec<LineReport> {
+        Validator::new(input.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_evaluate() {
+        let input = std::fs::read_to_string("example.in").unwrap();
+        let reports = validate(&input);
+        assert_eq!(reports.len(), input.lines().count());
+        for (report, line) in reports.iter().zip(input.lines()) {
+            assert_eq!(report.state, evaluate(line));
+        }
+    }
+
+    #[test]
+    fn positions() {
+        let reports = validate("([]\n\n{()>[[[\n<>");
+        assert_eq!(
+            reports,
+            vec![
+                LineReport {
+                    line: 1,
+                    column: 3,
+                    depth: 1,
+                    state: State::Incomplete,
+                    completion: vec![')'],
+                },
+                LineReport {
+                    line: 2,
+                    column: 0,
+                    depth: 0,
+                    state: State::Valid,
+                    completion: vec![],
+                },
+                LineReport {
+                    line: 3,
+                    column: 4,
+                    depth: 0,
+                    state: State::Corrupted('>'),
+                    completion: vec![],
+                },
+                LineReport {
+                    line: 4,
+                    column: 2,
+                    depth: 0,
+                    state: State::Valid,
+                    completion: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn long_line() {
+        let depth = 1_000_000;
+        let mut input = "(".repeat(depth);
+        input.push_str(&")".repeat(depth - 1));
+        input.push('\n');
+        let reports = validate(&input);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].state, State::Incomplete);
+        assert_eq!(reports[0].column, 2 * depth - 1);
+        assert_eq!(reports[0].completion, vec![')']);
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        let mut validator = Validator::new(&[b'(', 0xff, b')'][..]);
+        let err = validator.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(validator.next().is_none());
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let input = "([]\r\n{()>\r\n";
+        let mut validator = Validator::new(input.as_bytes());
+        let report = validator.next().unwrap().unwrap();
+        assert_eq!(report.state, State::InvalidChar(3, '\r'));
+        assert_eq!(report.column, 4);
+
+        let validator = Validator::with_policy(input.as_bytes(), InvalidCharPolicy::Ignore);
+        let states: Vec<State> = validator.map(|r| r.unwrap().state).collect();
+        assert_eq!(states, vec![State::Incomplete, State::Corrupted('>')]);
+    }
+}
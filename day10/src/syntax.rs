@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+
+// a string literal delimited by `delimiter` on both ends, in which
+// `escape` makes the following character literal
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringRule {
+    pub delimiter: char,
+    pub escape: Option<char>,
+}
+
+// lexical rules for the parts of a line whose brackets do not count.
+// modes are tracked per line, so block comments and strings have to be
+// closed on the line they were opened on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Syntax {
+    pub strings: Vec<StringRule>,
+    pub line_comments: Vec<String>,
+    pub block_comments: Vec<(String, String)>,
+}
+
+impl Syntax {
+    // double and single quoted strings with backslash escapes, `//` and
+    // `/* */` comments
+    pub fn c_like() -> Self {
+        Self {
+            strings: vec![
+                StringRule {
+                    delimiter: '"',
+                    escape: Some('\\'),
+                },
+                StringRule {
+                    delimiter: '\'',
+                    escape: Some('\\'),
+                },
+            ],
+            line_comments: vec!["//".to_string()],
+            block_comments: vec![("/*".to_string(), "*/".to_string())],
+        }
+    }
+
+    // every token that switches away from code, with the mode it enters
+    fn openers(&self) -> impl Iterator<Item = (String, Mode)> + '_ {
+        let strings = self.strings.iter().enumerate().map(|(rule, s)| {
+            let mode = Mode::Str {
+                rule,
+                escaped: false,
+            };
+            (s.delimiter.to_string(), mode)
+        });
+        let line_comments = self
+            .line_comments
+            .iter()
+            .map(|open| (open.clone(), Mode::LineComment));
+        let block_comments = self
+            .block_comments
+            .iter()
+            .enumerate()
+            .map(|(rule, (open, _))| (open.clone(), Mode::BlockComment { rule }));
+        strings.chain(line_comments).chain(block_comments)
+    }
+}
+
+// a character as seen by the bracket matcher
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lexeme {
+    Code(char),
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum Mode {
+    #[default]
+    Code,
+    Str {
+        rule: usize,
+        escaped: bool,
+    },
+    LineComment,
+    BlockComment {
+        rule: usize,
+    },
+}
+
+// splits a line into code and text according to a `Syntax`
+#[derive(Debug, Default)]
+pub struct Lexer {
+    syntax: Syntax,
+    mode: Mode,
+    // code that might still turn out to open a string or comment
+    pending: String,
+    // tail of the current block comment, to spot its closer
+    recent: String,
+    out: VecDeque<Lexeme>,
+}
+
+impl Lexer {
+    pub fn new(syntax: Syntax) -> Self {
+        Self {
+            syntax,
+            ..Default::default()
+        }
+    }
+
+    pub fn feed(&mut self, c: char) {
+        match self.mode {
+            Mode::Code => {
+                self.pending.push(c);
+                self.scan(false);
+            }
+            Mode::Str { rule, escaped } => {
+                self.out.push_back(Lexeme::Text);
+                let string = &self.syntax.strings[rule];
+                self.mode = if escaped {
+                    Mode::Str {
+                        rule,
+                        escaped: false,
+                    }
+                } else if string.escape == Some(c) {
+                    Mode::Str {
+                        rule,
+                        escaped: true,
+                    }
+                } else if string.delimiter == c {
+                    Mode::Code
+                } else {
+                    self.mode
+                };
+            }
+            Mode::LineComment => self.out.push_back(Lexeme::Text),
+            Mode::BlockComment { rule } => {
+                self.out.push_back(Lexeme::Text);
+                let close = &self.syntax.block_comments[rule].1;
+                self.recent.push(c);
+                if self.recent.ends_with(close.as_str()) {
+                    self.recent.clear();
+                    self.mode = Mode::Code;
+                } else if self.recent.chars().count() > close.chars().count() {
+                    self.recent.remove(0);
+                }
+            }
+        }
+    }
+
+    // release code held back in case it started a delimiter
+    pub fn finish(&mut self) {
+        self.scan(true);
+    }
+
+    pub fn next_lexeme(&mut self) -> Option<Lexeme> {
+        self.out.pop_front()
+    }
+
+    // the delimiter needed to close an open string or block comment
+    pub fn closer(&self) -> Option<String> {
+        match self.mode {
+            Mode::Str { rule, .. } => Some(self.syntax.strings[rule].delimiter.to_string()),
+            Mode::BlockComment { rule } => Some(self.syntax.block_comments[rule].1.clone()),
+            Mode::Code | Mode::LineComment => None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.mode = Mode::Code;
+        self.pending.clear();
+        self.recent.clear();
+        self.out.clear();
+    }
+
+    fn scan(&mut self, end_of_line: bool) {
+        while !self.pending.is_empty() {
+            let mut prefix = false;
+            for (open, mode) in self.syntax.openers() {
+                if open == self.pending {
+                    self.out.extend(self.pending.chars().map(|_| Lexeme::Text));
+                    self.pending.clear();
+                    self.mode = mode;
+                    return;
+                }
+                prefix |= open.starts_with(self.pending.as_str());
+            }
+            if prefix && !end_of_line {
+                return;
+            }
+            let first = self.pending.remove(0);
+            self.out.push_back(Lexeme::Code(first));
+        }
+    }
+}
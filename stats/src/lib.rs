@@ -0,0 +1,155 @@
+// order statistics shared by the puzzle solvers.
+//
+// the selection based functions take `&mut [T]` and reorder the values in
+// place instead of sorting them, which keeps them O(n).
+
+// values that can be averaged
+pub trait Sample: Copy + Ord {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_sample {
+    ($($t:ty),*) => {
+        $(impl Sample for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_sample!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// the middle of a non-empty set of values. an even number of values has
+// two middles, and the caller decides which one it wants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Median<T> {
+    Odd(T),
+    Even(T, T),
+}
+
+impl<T: Sample> Median<T> {
+    // the middle value, `None` for an even number of values
+    pub fn exact(self) -> Option<T> {
+        match self {
+            Median::Odd(m) => Some(m),
+            Median::Even(..) => None,
+        }
+    }
+
+    pub fn lower(self) -> T {
+        match self {
+            Median::Odd(m) | Median::Even(m, _) => m,
+        }
+    }
+
+    pub fn upper(self) -> T {
+        match self {
+            Median::Odd(m) | Median::Even(_, m) => m,
+        }
+    }
+
+    // mean of the two middles for an even number of values
+    pub fn mean(self) -> f64 {
+        match self {
+            Median::Odd(m) => m.to_f64(),
+            Median::Even(a, b) => (a.to_f64() + b.to_f64()) / 2.0,
+        }
+    }
+}
+
+// the k-th smallest value (0-based), `None` if there are not enough values
+pub fn select<T: Sample>(values: &mut [T], k: usize) -> Option<T> {
+    if k >= values.len() {
+        return None;
+    }
+    let (_, kth, _) = values.select_nth_unstable(k);
+    Some(*kth)
+}
+
+// `None` if there are no values
+pub fn median<T: Sample>(values: &mut [T]) -> Option<Median<T>> {
+    let n = values.len();
+    let upper = select(values, n / 2)?;
+    if n % 2 == 1 {
+        return Some(Median::Odd(upper));
+    }
+    // after selection everything left of the upper middle is no larger
+    // than it, so the lower middle is the largest of those
+    let lower = *values[..n / 2].iter().max()?;
+    Some(Median::Even(lower, upper))
+}
+
+// `None` if there are no values
+pub fn mean<T: Sample>(values: &[T]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let sum: f64 = values.iter().map(|v| v.to_f64()).sum();
+    Some(sum / values.len() as f64)
+}
+
+// nearest-rank percentile: the smallest value such that at least `p`
+// percent of the values are no larger. `None` if there are no values or
+// `p` is outside 0..=100.
+pub fn percentile<T: Sample>(values: &mut [T], p: f64) -> Option<T> {
+    if !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+    select(values, rank.max(1) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn odd_median() {
+        let mut scores = vec![288957, 5566, 1480781, 995444, 294];
+        assert_eq!(median(&mut scores), Some(Median::Odd(288957)));
+    }
+
+    #[test]
+    fn even_median() {
+        let mut values = vec![7, 1, 4, 3, 9, 2];
+        let m = median(&mut values).unwrap();
+        assert_eq!(m, Median::Even(3, 4));
+        assert_eq!(m.exact(), None);
+        assert_eq!(m.lower(), 3);
+        assert_eq!(m.upper(), 4);
+        assert_eq!(m.mean(), 3.5);
+    }
+
+    #[test]
+    fn empty() {
+        let mut values: Vec<u32> = Vec::new();
+        assert_eq!(median(&mut values), None);
+        assert_eq!(mean(&values), None);
+        assert_eq!(percentile(&mut values, 50.0), None);
+        assert_eq!(select(&mut values, 0), None);
+    }
+
+    #[test]
+    fn crab_alignment() {
+        // the median position minimises the summed distance of all crabs
+        let mut crabs: Vec<i64> = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        let target = median(&mut crabs).unwrap().lower();
+        assert_eq!(target, 2);
+        let fuel: i64 = crabs.iter().map(|c| (c - target).abs()).sum();
+        assert_eq!(fuel, 37);
+    }
+
+    #[test]
+    fn means_and_percentiles() {
+        let mut values: Vec<usize> = (1..=20).rev().collect();
+        assert_eq!(mean(&values), Some(10.5));
+        assert_eq!(percentile(&mut values, 0.0), Some(1));
+        assert_eq!(percentile(&mut values, 5.0), Some(1));
+        assert_eq!(percentile(&mut values, 25.0), Some(5));
+        assert_eq!(percentile(&mut values, 90.0), Some(18));
+        assert_eq!(percentile(&mut values, 100.0), Some(20));
+        assert_eq!(percentile(&mut values, 101.0), None);
+    }
+}